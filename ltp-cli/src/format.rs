@@ -0,0 +1,153 @@
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use ltp_rs::formats::{conll09, conllu, semeval, xml};
+use ltp_rs::preinclude::itertools::Itertools;
+use ltp_rs::preinclude::serde_json;
+use ltp_rs::{LTPError, LTPResult, Result};
+
+/// Formats of the results, written by `analyze` and `convert`, and read by `convert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// one JSON object per line and per sentence
+    Json,
+    Conllu,
+    Conll09,
+    Semeval,
+    /// the `xml4nlp` document of the older LTP releases, only written
+    Xml,
+    /// the words of a sentence per line, as `word/pos` with POS tags, only written
+    Plain,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> std::result::Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "conllu" => Ok(Format::Conllu),
+            "conll09" => Ok(Format::Conll09),
+            "semeval" => Ok(Format::Semeval),
+            "xml" => Ok(Format::Xml),
+            "plain" => Ok(Format::Plain),
+            _ => Err(format!(
+                "unknown format {}, expected json, conllu, conll09, semeval, xml or plain",
+                format
+            )),
+        }
+    }
+}
+
+impl Format {
+    /// Read the results of a document.
+    pub fn read<R: BufRead>(self, reader: R) -> Result<Vec<LTPResult>> {
+        match self {
+            Format::Json => reader
+                .lines()
+                .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+                .map(|line| Ok(serde_json::from_str(&line?)?))
+                .collect(),
+            Format::Conllu => conllu::read(reader),
+            Format::Conll09 => conll09::read(reader),
+            Format::Semeval => semeval::read(reader),
+            Format::Xml | Format::Plain => Err(LTPError::InvalidInput(format!(
+                "{:?} can only be written",
+                self
+            ))),
+        }
+    }
+}
+
+/// words of a sentence separated by spaces, as `word/pos` with POS tags
+fn plain(result: &LTPResult) -> String {
+    let mut words = result.seg.iter().flatten();
+    match result.pos.as_ref() {
+        Some(pos) => words
+            .zip(pos)
+            .map(|(word, pos)| format!("{}/{}", word, pos))
+            .join(" "),
+        None => words.join(" "),
+    }
+}
+
+/// Write results one by one, except XML documents which are written when finished, with a
+/// paragraph per input file.
+pub struct Writer<W: Write> {
+    format: Format,
+    output: W,
+    paragraphs: Vec<Vec<LTPResult>>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(format: Format, output: W) -> Self {
+        Writer {
+            format,
+            output,
+            paragraphs: Vec::new(),
+        }
+    }
+
+    /// Start the results of the next input file.
+    pub fn begin_file(&mut self) {
+        self.paragraphs.push(Vec::new());
+    }
+
+    pub fn write(&mut self, result: LTPResult) -> Result<()> {
+        let output = &mut self.output;
+        let results = std::slice::from_ref(&result);
+        match self.format {
+            Format::Json => writeln!(output, "{}", serde_json::to_string(&result)?)?,
+            Format::Conllu => conllu::write(output, results)?,
+            Format::Conll09 => conll09::write(output, results)?,
+            Format::Semeval => semeval::write(output, results)?,
+            Format::Plain => writeln!(output, "{}", plain(&result))?,
+            Format::Xml => match self.paragraphs.last_mut() {
+                Some(paragraph) => paragraph.push(result),
+                None => self.paragraphs.push(vec![result]),
+            },
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        if self.format == Format::Xml {
+            xml::write(&mut self.output, &self.paragraphs)?;
+        }
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{Format, Writer};
+    use ltp_rs::LTPResult;
+
+    #[test]
+    fn test_format() {
+        assert_eq!("CoNLLU".parse(), Ok(Format::Conllu));
+        assert!("conll".parse::<Format>().is_err());
+
+        let result = LTPResult {
+            seg: Some(vec!["他".into(), "叫".into(), "汤姆".into()]),
+            pos: Some(vec!["r".into(), "v".into(), "nh".into()]),
+            ..LTPResult::default()
+        };
+        let mut plain = Vec::new();
+        let mut writer = Writer::new(Format::Plain, &mut plain);
+        writer.write(result.clone()).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(plain).unwrap(), "他/r 叫/v 汤姆/nh\n");
+
+        let mut json = Vec::new();
+        let mut writer = Writer::new(Format::Json, &mut json);
+        writer.write(result.clone()).unwrap();
+        writer.write(result).unwrap();
+        writer.finish().unwrap();
+        let results = Format::Json.read(json.as_slice()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].pos.as_ref().unwrap()[2], "nh");
+        assert!(Format::Xml.read(json.as_slice()).is_err());
+    }
+}
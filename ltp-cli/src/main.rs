@@ -0,0 +1,273 @@
+mod format;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::Clap;
+use ltp_rs::preinclude::itertools::Itertools;
+use ltp_rs::preinclude::onnxruntime::GraphOptimizationLevel;
+use ltp_rs::preinclude::serde_json;
+use ltp_rs::{BatchPolicy, Result, Tasks, TensorInfo, LTP};
+
+use crate::format::{Format, Writer};
+
+#[derive(Clap)]
+#[clap(version = "1.0", author = "Feng Yunlong <ylfeng@ir.hit.edu.cn>")]
+struct Opts {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Clap)]
+enum Command {
+    /// Analyze the sentences of the input files, or of stdin, one sentence per line
+    Analyze(Analyze),
+    /// Convert results between formats
+    Convert(Convert),
+    /// Print the vocabularies and the ONNX inputs and outputs of a model
+    Inspect(Inspect),
+}
+
+#[derive(Clap)]
+struct ModelOpts {
+    /// Directory of the model, holding ltp.onnx, vocab.json and vocab.txt
+    #[clap(short, long, parse(from_os_str))]
+    model: PathBuf,
+    /// Threads of the ONNX Runtime session
+    #[clap(long, default_value = "4")]
+    threads: i16,
+    /// CUDA device of the session
+    #[cfg(feature = "cuda")]
+    #[clap(long, default_value = "0")]
+    device: i32,
+}
+
+impl ModelOpts {
+    #[cfg(not(feature = "cuda"))]
+    fn load(&self) -> Result<LTP> {
+        LTP::new_with_options(&self.model, GraphOptimizationLevel::All, self.threads)
+    }
+
+    #[cfg(feature = "cuda")]
+    fn load(&self) -> Result<LTP> {
+        LTP::new_with_cuda_options(
+            &self.model,
+            GraphOptimizationLevel::All,
+            self.threads,
+            self.device,
+        )
+    }
+}
+
+#[derive(Clap)]
+struct Analyze {
+    #[clap(flatten)]
+    model: ModelOpts,
+    /// Tasks separated by commas, among seg, pos, ner, srl, dep and sdp, or all
+    #[clap(short, long, default_value = "all", parse(try_from_str = parse_tasks))]
+    tasks: Tasks,
+    /// Output format, json, conllu, conll09, semeval, xml or plain
+    #[clap(short, long, default_value = "json")]
+    format: Format,
+    /// Sentences run together
+    #[clap(short, long, default_value = "8")]
+    batch_size: usize,
+    /// Max tokens of a batch once padded, sentences read together are split into batches of
+    /// similar lengths under it
+    #[clap(long)]
+    max_tokens: Option<usize>,
+    /// Output file, stdout by default
+    #[clap(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Input files, stdin when none is given or for `-`
+    #[clap(parse(from_os_str))]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Clap)]
+struct Convert {
+    /// Input format, json, conllu, conll09 or semeval
+    #[clap(long)]
+    from: Format,
+    /// Output format, json, conllu, conll09, semeval, xml or plain
+    #[clap(long)]
+    to: Format,
+    /// Output file, stdout by default
+    #[clap(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Input files, stdin when none is given or for `-`
+    #[clap(parse(from_os_str))]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Clap)]
+struct Inspect {
+    #[clap(flatten)]
+    model: ModelOpts,
+    /// Print a JSON object instead
+    #[clap(long)]
+    json: bool,
+}
+
+fn parse_tasks(tasks: &str) -> std::result::Result<Tasks, String> {
+    tasks.split(',').try_fold(Tasks::empty(), |tasks, task| {
+        let task = match task.trim().to_ascii_lowercase().as_str() {
+            "all" => Tasks::all(),
+            "seg" => Tasks::SEG,
+            "pos" => Tasks::POS,
+            "ner" => Tasks::NER,
+            "srl" => Tasks::SRL,
+            "dep" => Tasks::DEP,
+            "sdp" => Tasks::SDP,
+            _ => {
+                return Err(format!(
+                    "unknown task {}, expected seg, pos, ner, srl, dep, sdp or all",
+                    task
+                ))
+            }
+        };
+        Ok(tasks | task)
+    })
+}
+
+fn open(files: &[PathBuf]) -> Result<Vec<Box<dyn BufRead>>> {
+    if files.is_empty() {
+        return Ok(vec![Box::new(BufReader::new(io::stdin()))]);
+    }
+    files
+        .iter()
+        .map(|path| -> Result<Box<dyn BufRead>> {
+            match path.to_str() {
+                Some("-") => Ok(Box::new(BufReader::new(io::stdin()))),
+                _ => Ok(Box::new(BufReader::new(File::open(path)?))),
+            }
+        })
+        .collect()
+}
+
+fn create(output: Option<&PathBuf>) -> Result<Box<dyn Write>> {
+    match output {
+        Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        None => Ok(Box::new(BufWriter::new(io::stdout()))),
+    }
+}
+
+fn analyze(opts: Analyze) -> Result<()> {
+    let ltp = opts.model.load()?;
+    let mut writer = Writer::new(opts.format, create(opts.output.as_ref())?);
+    for input in open(&opts.files)? {
+        writer.begin_file();
+        let mut error = None;
+        let sentences = input
+            .lines()
+            .map_while(|line| line.map_err(|err| error = Some(err)).ok())
+            .filter(|line| !line.trim().is_empty());
+        for result in ltp
+            .analyze_iter(sentences, opts.batch_size)
+            .tasks(opts.tasks)
+            .batch_policy(BatchPolicy {
+                max_batch_size: opts.batch_size,
+                max_tokens: opts.max_tokens,
+            })
+        {
+            writer.write(result?)?;
+        }
+        if let Some(error) = error {
+            return Err(error.into());
+        }
+    }
+    writer.finish()
+}
+
+fn convert(opts: Convert) -> Result<()> {
+    let mut writer = Writer::new(opts.to, create(opts.output.as_ref())?);
+    for input in open(&opts.files)? {
+        writer.begin_file();
+        for result in opts.from.read(input)? {
+            writer.write(result)?;
+        }
+    }
+    writer.finish()
+}
+
+/// `name: type [dimensions]`, `?` for the dynamic dimensions
+fn describe(tensor: &TensorInfo) -> String {
+    let dimensions = tensor
+        .dimensions
+        .iter()
+        .map(|x| x.map_or_else(|| String::from("?"), |x| x.to_string()))
+        .join(", ");
+    format!("{}: {} [{}]", tensor.name, tensor.element_type, dimensions)
+}
+
+fn inspect(opts: Inspect) -> Result<()> {
+    let ltp = opts.model.load()?;
+    let signature = ltp.signature();
+    let vocabs = ltp.vocabs();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    if opts.json {
+        let inspected = serde_json::json!({
+            "signature": signature,
+            "vocabs": vocabs,
+        });
+        writeln!(output, "{}", serde_json::to_string_pretty(&inspected)?)?;
+        return Ok(());
+    }
+
+    writeln!(output, "inputs:")?;
+    for input in &signature.inputs {
+        writeln!(output, "    {}", describe(input))?;
+    }
+    writeln!(output, "outputs:")?;
+    for output_info in &signature.outputs {
+        writeln!(output, "    {}", describe(output_info))?;
+    }
+    writeln!(output, "vocabs:")?;
+    let tasks = [
+        ("seg", &vocabs.seg),
+        ("pos", &vocabs.pos),
+        ("ner", &vocabs.ner),
+        ("srl", &vocabs.srl),
+        ("dep", &vocabs.dep),
+        ("sdp", &vocabs.sdp),
+    ];
+    for (task, labels) in tasks.iter() {
+        match labels {
+            Some(labels) => writeln!(
+                output,
+                "    {} ({}): {}",
+                task,
+                labels.len(),
+                labels.join(" ")
+            )?,
+            None => writeln!(output, "    {}: -", task)?,
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    match Opts::parse().command {
+        Command::Analyze(opts) => analyze(opts),
+        Command::Convert(opts) => convert(opts),
+        Command::Inspect(opts) => inspect(opts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_tasks;
+    use ltp_rs::Tasks;
+
+    #[test]
+    fn test_parse_tasks() {
+        assert_eq!(
+            parse_tasks("seg,pos, DEP"),
+            Ok(Tasks::SEG | Tasks::POS | Tasks::DEP)
+        );
+        assert_eq!(parse_tasks("all"), Ok(Tasks::all()));
+        assert!(parse_tasks("seg,parser").is_err());
+    }
+}
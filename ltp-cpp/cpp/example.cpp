@@ -35,14 +35,17 @@ int main(int argc, char **argv) {
       for (auto &tag:result.ner()) cout << tag << "\t";
       cout << endl;
 
-      auto word_num = result.len();
-      for (auto i = 0; i < word_num; i++) {
-        for (auto &srls:result.srl(i)) {
-          cout << srls << "\t";
+      auto srl_num = result.srl_len();
+      for (auto i = 0; i < srl_num; i++) {
+        cout << result.srl_predicate(i) << "\t";
+        for (auto j = 0; j < result.srl_arg_len(i); j++) {
+          cout << result.srl_arg_role(i, j) << ":" << result.srl_arg_start(i, j) << ":"
+               << result.srl_arg_end(i, j) << "\t";
         }
         cout << endl;
       }
 
+      auto word_num = result.len();
       for (auto i = 0; i < word_num; i++) {
         cout << result.dep_arc(i) << ":" << result.dep_rel(i) << "\t";
       }
@@ -1,5 +1,5 @@
 use cxx::CxxString;
-use ltp_rs::{LTPResult, Result, LTP};
+use ltp_rs::{formats::conllu, sentence, LTPResult, Result, LTP};
 
 pub struct Interface(LTP);
 
@@ -7,18 +7,31 @@ pub struct InterfaceResult(LTPResult);
 
 #[cxx::bridge]
 mod ffi {
+    /// a sentence of the text with its `[start, end)` char offsets
+    struct Sentence {
+        text: String,
+        start: usize,
+        end: usize,
+    }
+
     extern "Rust" {
         type Interface;
         type InterfaceResult;
 
         fn ltp_init(path: &CxxString, num_threads: i32, device_id: i32) -> Result<Box<Interface>>;
-        fn pipeline(self: &mut Interface, sentences: &Vec<String>) -> Vec<InterfaceResult>;
+        fn split_sentences(text: &str) -> Vec<Sentence>;
+        fn pipeline(self: &Interface, sentences: &Vec<String>) -> Vec<InterfaceResult>;
         fn len(self: &InterfaceResult) -> usize;
 
         fn seg(self: &InterfaceResult) -> &Vec<String>;
         fn pos(self: &InterfaceResult) -> &Vec<String>;
         fn ner(self: &InterfaceResult) -> &Vec<String>;
-        fn srl(self: &InterfaceResult, idx: usize) -> &Vec<String>;
+        fn srl_len(self: &InterfaceResult) -> usize;
+        fn srl_predicate(self: &InterfaceResult, idx: usize) -> usize;
+        fn srl_arg_len(self: &InterfaceResult, idx: usize) -> usize;
+        fn srl_arg_role(self: &InterfaceResult, idx: usize, arg: usize) -> &String;
+        fn srl_arg_start(self: &InterfaceResult, idx: usize, arg: usize) -> usize;
+        fn srl_arg_end(self: &InterfaceResult, idx: usize, arg: usize) -> usize;
         fn dep_arc(self: &InterfaceResult, idx: usize) -> usize;
         fn dep_rel(self: &InterfaceResult, idx: usize) -> &String;
 
@@ -26,6 +39,8 @@ mod ffi {
         fn sdp_src(self: &InterfaceResult, idx: usize) -> usize;
         fn sdp_tgt(self: &InterfaceResult, idx: usize) -> usize;
         fn sdp_rel(self: &InterfaceResult, idx: usize) -> &String;
+
+        fn to_conllu(self: &InterfaceResult) -> String;
     }
 }
 
@@ -51,8 +66,19 @@ fn ltp_init(path: &CxxString, num_threads: i32, device_id: i32) -> Result<Box<In
     )?)));
 }
 
+fn split_sentences(text: &str) -> Vec<ffi::Sentence> {
+    sentence::split_sentences(text)
+        .into_iter()
+        .map(|sentence| ffi::Sentence {
+            text: sentence.text.to_string(),
+            start: sentence.char_offsets.0,
+            end: sentence.char_offsets.1,
+        })
+        .collect()
+}
+
 impl Interface {
-    pub fn pipeline(&mut self, sentences: &Vec<String>) -> Vec<InterfaceResult> {
+    pub fn pipeline(&self, sentences: &Vec<String>) -> Vec<InterfaceResult> {
         match self.0.pipeline_batch(sentences) {
             Ok(results) => {
                 let mut res: Vec<InterfaceResult> = vec![];
@@ -81,8 +107,23 @@ impl InterfaceResult {
     pub fn ner(&self) -> &Vec<String> {
         self.0.ner.as_ref().unwrap()
     }
-    pub fn srl(&self, idx: usize) -> &Vec<String> {
-        self.0.srl.as_ref().unwrap()[idx].as_ref()
+    pub fn srl_len(&self) -> usize {
+        self.0.srl.as_ref().unwrap().len()
+    }
+    pub fn srl_predicate(&self, idx: usize) -> usize {
+        self.0.srl.as_ref().unwrap()[idx].predicate
+    }
+    pub fn srl_arg_len(&self, idx: usize) -> usize {
+        self.0.srl.as_ref().unwrap()[idx].arguments.len()
+    }
+    pub fn srl_arg_role(&self, idx: usize, arg: usize) -> &String {
+        &self.0.srl.as_ref().unwrap()[idx].arguments[arg].role
+    }
+    pub fn srl_arg_start(&self, idx: usize, arg: usize) -> usize {
+        self.0.srl.as_ref().unwrap()[idx].arguments[arg].start
+    }
+    pub fn srl_arg_end(&self, idx: usize, arg: usize) -> usize {
+        self.0.srl.as_ref().unwrap()[idx].arguments[arg].end
     }
     pub fn dep_arc(&self, idx: usize) -> usize {
         self.0.dep.as_ref().unwrap()[idx].arc
@@ -103,6 +144,10 @@ impl InterfaceResult {
     pub fn sdp_rel(&self, idx: usize) -> &String {
         &self.0.sdp.as_ref().unwrap()[idx].rel
     }
+
+    pub fn to_conllu(&self) -> String {
+        conllu::to_string(std::slice::from_ref(&self.0))
+    }
 }
 
 #[cfg(test)]
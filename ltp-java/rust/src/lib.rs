@@ -1,13 +1,15 @@
 use jni::{
     errors::Error as JNIError,
-    objects::{JClass, JList, JObject, JString},
+    objects::{JClass, JList, JObject, JString, JValue},
     sys::{jlong, jobject},
     JNIEnv,
 };
 
 use jni::sys::jint;
 use ltp_rs::{
+    formats::conllu,
     preinclude::thiserror::{self, Error},
+    sentence::split_sentences,
     LTPError, LTP as Interface,
 };
 
@@ -65,13 +67,8 @@ fn ltp_rust_init(
     Ok(Box::into_raw(interface) as jlong)
 }
 
-fn ltp_rust_pipeline(
-    env: JNIEnv,
-    _class: JClass,
-    ptr: jlong,
-    sentences: JObject,
-) -> Result<jobject> {
-    let sentences = JList::from_env(&env, sentences)?;
+fn java_sentences(env: &JNIEnv, sentences: JObject) -> Result<Vec<String>> {
+    let sentences = JList::from_env(env, sentences)?;
     let length = sentences.size()?.into();
     let mut batch_sentences = Vec::new();
     for idx in 0..length {
@@ -80,15 +77,25 @@ fn ltp_rust_pipeline(
         let sentence: String = env.get_string(sentence)?.into();
         batch_sentences.push(sentence);
     }
+    Ok(batch_sentences)
+}
 
-    let mut interface = unsafe {
-        let ptr = ptr as *mut Interface;
+fn interface<'a>(ptr: jlong) -> &'a Interface {
+    unsafe {
+        let ptr = ptr as *const Interface;
         assert!(!ptr.is_null());
-        Box::from_raw(ptr)
-    };
+        &*ptr
+    }
+}
 
-    let results = interface.pipeline_batch(&batch_sentences)?;
-    Box::into_raw(interface);
+fn ltp_rust_pipeline(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    sentences: JObject,
+) -> Result<jobject> {
+    let batch_sentences = java_sentences(&env, sentences)?;
+    let results = interface(ptr).pipeline_batch(&batch_sentences)?;
 
     let java_list = new_java_list(&env)?;
     for result in results {
@@ -120,12 +127,30 @@ fn ltp_rust_pipeline(
 
         let srl = new_java_list(&env)?;
         if result.srl.is_some() {
-            for tags in result.srl.unwrap() {
-                let srl_tags = new_java_list(&env)?;
-                for tag in tags {
-                    srl_tags.add(env.new_string(tag)?.into())?;
+            for frame in result.srl.unwrap() {
+                let arguments = new_java_list(&env)?;
+                for arg in frame.arguments {
+                    let role = env.new_string(arg.role)?;
+                    let arg = env.new_object(
+                        "cn/edu/hit/ir/SrlArg",
+                        "(Ljava/lang/String;II)V",
+                        &[
+                            JValue::from(role),
+                            JValue::Int(arg.start as jint),
+                            JValue::Int(arg.end as jint),
+                        ],
+                    )?;
+                    arguments.add(arg)?;
                 }
-                srl.add(JObject::from(srl_tags))?;
+                let frame = env.new_object(
+                    "cn/edu/hit/ir/SrlFrame",
+                    "(ILjava/util/List;)V",
+                    &[
+                        JValue::Int(frame.predicate as jint),
+                        JValue::from(JObject::from(arguments)),
+                    ],
+                )?;
+                srl.add(frame)?;
             }
         }
         java_one_list.add(JObject::from(srl))?;
@@ -155,6 +180,38 @@ fn ltp_rust_pipeline(
     Ok(java_list.into_inner())
 }
 
+fn ltp_rust_to_conllu(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    sentences: JObject,
+) -> Result<jobject> {
+    let batch_sentences = java_sentences(&env, sentences)?;
+    let results = interface(ptr).pipeline_batch(&batch_sentences)?;
+    Ok(env.new_string(conllu::to_string(&results))?.into_inner())
+}
+
+fn ltp_rust_split_sentences(env: JNIEnv, _class: JClass, text: JString) -> Result<jobject> {
+    let text: String = env.get_string(text)?.into();
+
+    let java_list = new_java_list(&env)?;
+    for sentence in split_sentences(&text) {
+        let (start, end) = sentence.char_offsets;
+        let text = env.new_string(sentence.text)?;
+        let sentence = env.new_object(
+            "cn/edu/hit/ir/Sentence",
+            "(Ljava/lang/String;II)V",
+            &[
+                JValue::from(text),
+                JValue::Int(start as jint),
+                JValue::Int(end as jint),
+            ],
+        )?;
+        java_list.add(sentence)?;
+    }
+    Ok(java_list.into_inner())
+}
+
 #[no_mangle]
 pub extern "system" fn Java_cn_edu_hit_ir_LTP_rust_1init(
     env: JNIEnv,
@@ -213,6 +270,41 @@ pub extern "system" fn Java_cn_edu_hit_ir_LTP_rust_1pipeline(
     }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_cn_edu_hit_ir_LTP_rust_1to_1conllu(
+    env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    sentences: JObject,
+) -> jobject {
+    let result = ltp_rust_to_conllu(env, _class, ptr, sentences);
+    match result {
+        Ok(res) => res,
+        Err(err) => {
+            env.throw_new("java/lang/Exception", format!("{}", err))
+                .expect("jni native error!");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_cn_edu_hit_ir_LTP_rust_1split_1sentences(
+    env: JNIEnv,
+    _class: JClass,
+    text: JString,
+) -> jobject {
+    let result = ltp_rust_split_sentences(env, _class, text);
+    match result {
+        Ok(res) => res,
+        Err(err) => {
+            env.throw_new("java/lang/Exception", format!("{}", err))
+                .expect("jni native error!");
+            std::ptr::null_mut()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -50,6 +50,15 @@ public class LTP {
         return rust_pipeline(ptr, sentences);
     }
 
+    public String toConllu(List sentences) throws Exception {
+        return rust_to_conllu(ptr, sentences);
+    }
+
+    // 分句，返回 Sentence 列表
+    public static List splitSentences(String text) throws Exception {
+        return rust_split_sentences(text);
+    }
+
 
     public static void main(String[] args) {
         LTP ltp = null;
@@ -69,4 +78,8 @@ public class LTP {
     private static native void rust_release(long ptr);
 
     private native List rust_pipeline(long ptr, List sentences) throws Exception;
+
+    private native String rust_to_conllu(long ptr, List sentences) throws Exception;
+
+    private static native List rust_split_sentences(String text) throws Exception;
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+package cn.edu.hit.ir;
+
+// 分句结果，start 和 end 为句子在原文中 [start, end) 的字符（码点）下标
+public class Sentence {
+    public final String text;
+    public final int start;
+    public final int end;
+
+    public Sentence(String text, int start, int end) {
+        this.text = text;
+        this.start = start;
+        this.end = end;
+    }
+
+    @Override
+    public String toString() {
+        return "(" + text + ", " + start + ", " + end + ")";
+    }
+}
@@ -0,0 +1,19 @@
+package cn.edu.hit.ir;
+
+// 谓词的一个论元，覆盖 start..=end 的词
+public class SrlArg {
+    public final String role;
+    public final int start;
+    public final int end;
+
+    public SrlArg(String role, int start, int end) {
+        this.role = role;
+        this.start = start;
+        this.end = end;
+    }
+
+    @Override
+    public String toString() {
+        return "(" + role + ", " + start + ", " + end + ")";
+    }
+}
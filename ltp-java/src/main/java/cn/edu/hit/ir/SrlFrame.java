@@ -0,0 +1,19 @@
+package cn.edu.hit.ir;
+
+import java.util.List;
+
+// 谓词及其论元，词的下标与分词结果一致，从 0 开始
+public class SrlFrame {
+    public final int predicate;
+    public final List<SrlArg> arguments;
+
+    public SrlFrame(int predicate, List<SrlArg> arguments) {
+        this.predicate = predicate;
+        this.arguments = arguments;
+    }
+
+    @Override
+    public String toString() {
+        return "(" + predicate + ", " + arguments + ")";
+    }
+}
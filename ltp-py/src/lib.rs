@@ -1,5 +1,3 @@
-use std::sync::{Arc, Mutex};
-
 use pyo3::{
     exceptions::PyOSError,
     prelude::*,
@@ -8,14 +6,22 @@ use pyo3::{
 };
 
 use ltp_rs::{
-    eisner::eisner,
+    dictionary::{Dictionary, MatchStrategy},
+    eisner::{eisner, eisner_inside_outside},
     entities::get_entities,
+    formats::conllu,
+    mst::mst,
     preinclude::{
         itertools::Itertools,
+        onnxruntime::GraphOptimizationLevel,
         thiserror::{self, Error},
     },
-    viterbi::viterbi_decode_postprocess,
-    LTPError, LTP as Interface,
+    sentence::split_sentences,
+    viterbi::{
+        crf_forward, crf_marginals, viterbi_decode, viterbi_decode_kbest,
+        viterbi_decode_postprocess,
+    },
+    LTPError, RootPolicy, SdpPolicy, TreeDecoder, LTP as Interface,
 };
 
 /// Error type centralizing all possible errors
@@ -28,13 +34,9 @@ enum PyO3LTPError {
 
 #[pyclass]
 struct LTP {
-    interface: Arc<Mutex<Interface>>,
+    interface: Interface,
 }
 
-unsafe impl Send for LTP {}
-
-unsafe impl Sync for LTP {}
-
 impl From<LTPError> for PyO3LTPError {
     fn from(err: LTPError) -> PyO3LTPError {
         PyO3LTPError::LTPError(err)
@@ -50,34 +52,132 @@ impl From<PyO3LTPError> for PyErr {
 #[pymethods]
 impl LTP {
     #[new]
-    #[args(num_threads = 1, device_id = "None")]
-    fn new(path: &PyUnicode, num_threads: i32, device_id: Option<i32>) -> PyResult<Self> {
+    #[args(num_threads = 1, device_id = "None", pool_size = 1)]
+    fn new(
+        path: &PyUnicode,
+        num_threads: i32,
+        device_id: Option<i32>,
+        pool_size: usize,
+    ) -> PyResult<Self> {
         #[cfg(feature = "cuda")]
         let interface = match device_id {
-            None => Interface::new(&path.to_string(), num_threads as i16)
-                .map_err(|e| PyO3LTPError::from(LTPError::from(e)))?,
-            Some(n) => Interface::new_with_cuda(&path.to_string(), num_threads as i16, n)
-                .map_err(|e| PyO3LTPError::from(LTPError::from(e)))?,
+            None => Interface::new_with_pool_options(
+                &path.to_string(),
+                GraphOptimizationLevel::All,
+                num_threads as i16,
+                pool_size,
+            )
+            .map_err(|e| PyO3LTPError::from(LTPError::from(e)))?,
+            Some(n) => Interface::new_with_cuda_pool_options(
+                &path.to_string(),
+                GraphOptimizationLevel::All,
+                num_threads as i16,
+                n,
+                pool_size,
+            )
+            .map_err(|e| PyO3LTPError::from(LTPError::from(e)))?,
         };
         #[cfg(not(feature = "cuda"))]
-        let interface = Interface::new(&path.to_string(), num_threads as i16)
-            .map_err(|e| PyO3LTPError::from(LTPError::from(e)))?;
-        Ok(LTP {
-            interface: Arc::new(Mutex::new(interface)),
-        })
+        let interface = Interface::new_with_pool_options(
+            &path.to_string(),
+            GraphOptimizationLevel::All,
+            num_threads as i16,
+            pool_size,
+        )
+        .map_err(|e| PyO3LTPError::from(LTPError::from(e)))?;
+        Ok(LTP { interface })
     }
 
-    fn pipeline(&self, py: Python, ob: PyObject) -> PyResult<PyObject> {
+    #[args(weighted = false)]
+    fn load_dictionary(&mut self, path: &PyUnicode, weighted: bool) -> PyResult<()> {
+        let strategy = match weighted {
+            true => MatchStrategy::Weighted,
+            false => MatchStrategy::Longest,
+        };
+        let dictionary =
+            Dictionary::load(path.to_string(), strategy).map_err(|e| PyO3LTPError::from(e))?;
+
+        self.interface.set_dictionary(Some(dictionary));
+        Ok(())
+    }
+
+    fn set_confidence(&mut self, confidence: bool) -> PyResult<()> {
+        self.interface
+            .set_confidence(confidence)
+            .map_err(|e| PyO3LTPError::from(e))?;
+        Ok(())
+    }
+
+    fn set_kbest(&mut self, k: usize) -> PyResult<()> {
+        self.interface
+            .set_kbest(k)
+            .map_err(|e| PyO3LTPError::from(e))?;
+        Ok(())
+    }
+
+    fn set_non_projective(&mut self, non_projective: bool) {
+        self.interface.set_tree_decoder(match non_projective {
+            true => TreeDecoder::NonProjective,
+            false => TreeDecoder::Projective,
+        });
+    }
+
+    fn set_multi_root(&mut self, multi_root: bool) {
+        self.interface.set_root_policy(match multi_root {
+            true => RootPolicy::Multiple,
+            false => RootPolicy::Single,
+        });
+    }
+
+    fn set_joint_labels(&mut self, joint_labels: bool) -> PyResult<()> {
+        self.interface
+            .set_joint_labels(joint_labels)
+            .map_err(|e| PyO3LTPError::from(e))?;
+        Ok(())
+    }
+
+    #[args(
+        threshold = "0.0",
+        max_parents = "None",
+        backbone = true,
+        connect = false,
+        remove_cycles = false
+    )]
+    fn set_sdp_policy(
+        &mut self,
+        threshold: f32,
+        max_parents: Option<usize>,
+        backbone: bool,
+        connect: bool,
+        remove_cycles: bool,
+    ) {
+        self.interface.set_sdp_policy(SdpPolicy {
+            threshold,
+            max_parents,
+            backbone,
+            connect,
+            remove_cycles,
+        });
+    }
+
+    fn to_conllu(&self, py: Python, ob: PyObject) -> PyResult<String> {
         let ob: &PyList = ob.cast_as(py)?;
         let array = ob.iter().map(|x| x.to_string()).collect_vec();
 
-        let mut interface_guard = self
-            .interface
-            .lock()
-            .expect("Failed to acquire lock: another thread panicked?");
+        let interface = &self.interface;
+        let results = py
+            .allow_threads(|| interface.pipeline_batch(&array))
+            .map_err(|e| PyO3LTPError::from(e))?;
+        Ok(conllu::to_string(&results))
+    }
 
-        let results = interface_guard
-            .pipeline_batch(&array)
+    fn pipeline(&self, py: Python, ob: PyObject) -> PyResult<PyObject> {
+        let ob: &PyList = ob.cast_as(py)?;
+        let array = ob.iter().map(|x| x.to_string()).collect_vec();
+
+        let interface = &self.interface;
+        let results = py
+            .allow_threads(|| interface.pipeline_batch(&array))
             .map_err(|e| PyO3LTPError::from(e))?;
 
         let list = PyList::empty(py);
@@ -93,6 +193,22 @@ impl LTP {
             }
             one_sentence.set_item("seg", seg)?;
 
+            let char_offsets = PyList::empty(py);
+            if result.char_offsets.is_some() {
+                for offset in result.char_offsets.unwrap() {
+                    char_offsets.append(offset)?;
+                }
+            }
+            one_sentence.set_item("char_offsets", char_offsets)?;
+
+            let byte_offsets = PyList::empty(py);
+            if result.byte_offsets.is_some() {
+                for offset in result.byte_offsets.unwrap() {
+                    byte_offsets.append(offset)?;
+                }
+            }
+            one_sentence.set_item("byte_offsets", byte_offsets)?;
+
             let pos = PyList::empty(py);
             if result.pos.is_some() {
                 for tag in result.pos.unwrap() {
@@ -109,14 +225,29 @@ impl LTP {
             }
             one_sentence.set_item("ner", ner)?;
 
+            let entities = PyList::empty(py);
+            if result.entities.is_some() {
+                for entity in result.entities.unwrap() {
+                    entities.append((
+                        entity.label,
+                        entity.start_word,
+                        entity.end_word,
+                        entity.text,
+                        entity.char_start,
+                        entity.char_end,
+                    ))?;
+                }
+            }
+            one_sentence.set_item("entities", entities)?;
+
             let srl = PyList::empty(py);
             if result.srl.is_some() {
-                for tags in result.srl.unwrap() {
-                    let srl_tags = PyList::empty(py);
-                    for tag in tags {
-                        srl_tags.append(tag)?;
+                for frame in result.srl.unwrap() {
+                    let arguments = PyList::empty(py);
+                    for arg in frame.arguments {
+                        arguments.append((arg.role, arg.start, arg.end))?;
                     }
-                    srl.append(srl_tags)?;
+                    srl.append((frame.predicate, arguments))?;
                 }
             }
             one_sentence.set_item("srl", srl)?;
@@ -137,6 +268,26 @@ impl LTP {
             }
             one_sentence.set_item("sdp", sdp)?;
 
+            if let Some(confidence) = result.confidence {
+                let scores = PyDict::new(py);
+                scores.set_item("seg", confidence.seg)?;
+                scores.set_item("pos", confidence.pos)?;
+                scores.set_item("ner", confidence.ner)?;
+                scores.set_item("srl", confidence.srl)?;
+                scores.set_item("dep", confidence.dep)?;
+                scores.set_item("sdp", confidence.sdp)?;
+                one_sentence.set_item("confidence", scores)?;
+            }
+
+            if let Some(kbest) = result.kbest {
+                let alternatives = PyDict::new(py);
+                alternatives.set_item("seg", kbest.seg)?;
+                alternatives.set_item("srl", kbest.srl)?;
+                alternatives.set_item("dep", kbest.dep)?;
+                alternatives.set_item("sdp", kbest.sdp)?;
+                one_sentence.set_item("kbest", alternatives)?;
+            }
+
             list.append(one_sentence)?;
         }
         Ok(list.to_object(py))
@@ -153,6 +304,22 @@ fn py_eisner(scores: Vec<f32>, stn_length: Vec<usize>) -> PyResult<Vec<Vec<usize
     Ok(eisner(scores.as_slice(), stn_length.as_slice(), true))
 }
 
+#[pyfunction]
+fn py_eisner_inside_outside(
+    scores: Vec<f32>,
+    stn_length: Vec<usize>,
+) -> PyResult<(Vec<f32>, Vec<f32>)> {
+    Ok(eisner_inside_outside(
+        scores.as_slice(),
+        stn_length.as_slice(),
+    ))
+}
+
+#[pyfunction]
+fn py_mst(scores: Vec<f32>, stn_length: Vec<usize>) -> PyResult<Vec<Vec<usize>>> {
+    Ok(mst(scores.as_slice(), stn_length.as_slice(), true))
+}
+
 #[pyfunction]
 fn py_viterbi_decode_postprocess(
     history: Vec<i64>,
@@ -168,11 +335,107 @@ fn py_viterbi_decode_postprocess(
     ))
 }
 
+#[pyfunction(start = "None", end = "None")]
+fn py_viterbi_decode(
+    emissions: Vec<f32>,
+    transitions: Vec<f32>,
+    stn_lengths: Vec<usize>,
+    label_num: usize,
+    start: Option<Vec<f32>>,
+    end: Option<Vec<f32>>,
+) -> PyResult<Vec<(Vec<usize>, f32)>> {
+    Ok(viterbi_decode(
+        emissions.as_slice(),
+        transitions.as_slice(),
+        start.as_deref(),
+        end.as_deref(),
+        &stn_lengths,
+        label_num,
+    ))
+}
+
+#[pyfunction(start = "None", end = "None")]
+fn py_viterbi_decode_kbest(
+    emissions: Vec<f32>,
+    transitions: Vec<f32>,
+    stn_lengths: Vec<usize>,
+    label_num: usize,
+    k: usize,
+    start: Option<Vec<f32>>,
+    end: Option<Vec<f32>>,
+) -> PyResult<Vec<Vec<(Vec<usize>, f32)>>> {
+    Ok(viterbi_decode_kbest(
+        emissions.as_slice(),
+        transitions.as_slice(),
+        start.as_deref(),
+        end.as_deref(),
+        &stn_lengths,
+        label_num,
+        k,
+    ))
+}
+
+#[pyfunction(start = "None", end = "None")]
+fn py_crf_forward(
+    emissions: Vec<f32>,
+    transitions: Vec<f32>,
+    stn_lengths: Vec<usize>,
+    label_num: usize,
+    start: Option<Vec<f32>>,
+    end: Option<Vec<f32>>,
+) -> PyResult<Vec<f32>> {
+    Ok(crf_forward(
+        emissions.as_slice(),
+        transitions.as_slice(),
+        start.as_deref(),
+        end.as_deref(),
+        &stn_lengths,
+        label_num,
+    ))
+}
+
+#[pyfunction(start = "None", end = "None")]
+fn py_crf_marginals(
+    emissions: Vec<f32>,
+    transitions: Vec<f32>,
+    stn_lengths: Vec<usize>,
+    label_num: usize,
+    start: Option<Vec<f32>>,
+    end: Option<Vec<f32>>,
+) -> PyResult<Vec<Vec<f32>>> {
+    Ok(crf_marginals(
+        emissions.as_slice(),
+        transitions.as_slice(),
+        start.as_deref(),
+        end.as_deref(),
+        &stn_lengths,
+        label_num,
+    ))
+}
+
+#[pyfunction]
+fn py_split_sentences<'a>(text: &'a str) -> PyResult<Vec<(&'a str, usize, usize)>> {
+    Ok(split_sentences(text)
+        .into_iter()
+        .map(|sentence| {
+            let (start, end) = sentence.char_offsets;
+            (sentence.text, start, end)
+        })
+        .collect_vec())
+}
+
 #[pymodule]
 fn pyltp(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<LTP>()?;
     m.add_function(wrap_pyfunction!(py_eisner, m)?)?;
+    m.add_function(wrap_pyfunction!(py_eisner_inside_outside, m)?)?;
+    m.add_function(wrap_pyfunction!(py_mst, m)?)?;
     m.add_function(wrap_pyfunction!(py_get_entities, m)?)?;
     m.add_function(wrap_pyfunction!(py_viterbi_decode_postprocess, m)?)?;
+    m.add_function(wrap_pyfunction!(py_viterbi_decode, m)?)?;
+    m.add_function(wrap_pyfunction!(py_viterbi_decode_kbest, m)?)?;
+    m.add_function(wrap_pyfunction!(py_crf_forward, m)?)?;
+    m.add_function(wrap_pyfunction!(py_crf_marginals, m)?)?;
+    m.add_function(wrap_pyfunction!(py_split_sentences, m)?)?;
     Ok(())
 }
@@ -0,0 +1,30 @@
+import os
+
+import pytest
+
+from pyltp import LTP
+
+MODEL = os.environ.get(
+    "LTP_MODEL",
+    os.path.join(os.path.dirname(__file__), "..", "..", "models", "small"),
+)
+
+pytestmark = pytest.mark.skipif(not os.path.isdir(MODEL), reason="no model at LTP_MODEL")
+
+
+def test_dictionary_constrains_every_task(tmp_path):
+    ltp = LTP(MODEL)
+    lexicon = tmp_path / "lexicon.txt"
+    lexicon.write_text("汤姆去\n", encoding="utf-8")
+    ltp.load_dictionary(str(lexicon))
+
+    result = ltp.pipeline(["他叫汤姆去拿外衣。"])[0]
+    words = len(result["seg"])
+    assert "汤姆去" in result["seg"]
+    assert len(result["pos"]) == words
+    assert len(result["ner"]) == words
+    assert len(result["dep"]) == words
+    assert all(0 <= arc <= words for arc, _rel in result["dep"])
+    for predicate, arguments in result["srl"]:
+        assert predicate < words
+        assert all(start <= end < words for _role, start, end in arguments)
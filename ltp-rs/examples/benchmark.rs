@@ -4,8 +4,9 @@ use ltp_rs::preinclude::onnxruntime::GraphOptimizationLevel;
 use ltp_rs::preinclude::serde_json;
 use ltp_rs::{LTPError, LTP};
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Write};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 #[derive(Clap)]
@@ -27,39 +28,39 @@ where
     Ok(io::BufReader::new(file).lines())
 }
 
-// todo ensure output format
-
 fn main() -> Result<(), LTPError> {
     let opts: Opts = Opts::parse();
 
     let model_path = opts.model;
     #[cfg(feature = "cuda")]
-    let mut ltp = LTP::new_with_cuda_options(&model_path, GraphOptimizationLevel::All, 16, 0)?;
+    let ltp = LTP::new_with_cuda_options(&model_path, GraphOptimizationLevel::All, 16, 0)?;
 
     #[cfg(not(feature = "cuda"))]
-    let mut ltp = LTP::new_with_options(&model_path, GraphOptimizationLevel::All, 16)?;
+    let ltp = LTP::new_with_options(&model_path, GraphOptimizationLevel::All, 16)?;
     let batch_size = opts.batch_size;
 
     let start = Instant::now();
     match opts.file {
         Some(path) => {
-            // File hosts must exist in current path before this produces output
-            if let Ok(lines) = read_lines(path) {
-                // Consumes the iterator, returns an (Optional) String
-                let bar = ProgressBar::new(10000);
-                let mut batch = vec![];
-                for (idx, line) in lines.enumerate() {
-                    if let Ok(line) = line {
-                        batch.push(line)
-                    }
-                    if (idx + 1) % batch_size == 0 {
-                        // todo output
-                        ltp.pipeline_batch(&batch)?;
-                        bar.inc(batch_size as u64);
-                        batch.clear();
-                    }
-                }
-                bar.finish();
+            let lines = read_lines(path)?;
+            let stdout = io::stdout();
+            let mut output = stdout.lock();
+            let bar = ProgressBar::new_spinner();
+            // the lines are read in the prefetching thread, which stops at the first error
+            let error = Arc::new(Mutex::new(None));
+            let read_error = error.clone();
+            let inputs = lines.map_while(move |line| {
+                line.map_err(|err| *read_error.lock().unwrap() = Some(err))
+                    .ok()
+            });
+            for result in ltp.analyze_iter(inputs, batch_size).prefetch() {
+                writeln!(output, "{}", serde_json::to_string(&result?)?)?;
+                bar.inc(1);
+            }
+            bar.finish();
+            let error = error.lock().unwrap().take();
+            if let Some(error) = error {
+                return Err(error.into());
             }
         }
         None => {
@@ -75,7 +76,7 @@ fn main() -> Result<(), LTPError> {
         }
     }
     let duration = start.elapsed();
-    println!(
+    eprintln!(
         "Done! Cost: {}s",
         duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
     );
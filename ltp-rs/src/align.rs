@@ -0,0 +1,236 @@
+//! The word level heads of the model are pooled from its own segmentation, their outputs are
+//! projected here onto other words of the same text: the words given as pre-segmented input,
+//! or the segmentation constrained by a user dictionary.
+
+use itertools::Itertools;
+use onnxruntime::ndarray::{ArrayD, ArrayViewD, Axis, IxDyn};
+
+use crate::entities::get_entities;
+
+/// chars shared by two `[start, end)` spans
+fn overlap(x: (usize, usize), y: (usize, usize)) -> usize {
+    x.1.min(y.1).saturating_sub(x.0.max(y.0))
+}
+
+/// last word starting before `span`, or the first word
+fn nearest(words: &[(usize, usize)], span: (usize, usize)) -> usize {
+    words.iter().rposition(|x| x.0 <= span.0).unwrap_or(0)
+}
+
+/// How the given words of a text overlap the words the model segmented, both as `[start, end)`
+/// char spans. A word overlapping no word of the other side is aligned to the nearest one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Alignment {
+    /// model words of every given word
+    cover: Vec<Vec<usize>>,
+    /// model word of every given word sharing the most chars with it, the first one on ties
+    main: Vec<usize>,
+    /// first and last given words of every model word
+    span: Vec<(usize, usize)>,
+    /// given word of every model word sharing the most chars with it, the first one on ties
+    owner: Vec<usize>,
+}
+
+impl Alignment {
+    /// `model` must hold a word when `given` does.
+    pub(crate) fn new(given: &[(usize, usize)], model: &[(usize, usize)]) -> Alignment {
+        let cover = given
+            .iter()
+            .map(|&word| {
+                let overlapping = (0..model.len())
+                    .filter(|&m| overlap(word, model[m]) > 0)
+                    .collect_vec();
+                match overlapping.is_empty() {
+                    true => vec![nearest(model, word)],
+                    false => overlapping,
+                }
+            })
+            .collect_vec();
+        let main = cover
+            .iter()
+            .zip(given)
+            .map(|(cover, &word)| {
+                *cover
+                    .iter()
+                    .rev()
+                    .max_by_key(|&&m| overlap(word, model[m]))
+                    .unwrap()
+            })
+            .collect_vec();
+        let span = (0..model.len())
+            .map(|m| {
+                let mut covering = (0..given.len()).filter(|&g| cover[g].contains(&m));
+                match (covering.next(), covering.next_back()) {
+                    (Some(first), Some(last)) => (first, last),
+                    (Some(first), None) => (first, first),
+                    _ => (nearest(given, model[m]), nearest(given, model[m])),
+                }
+            })
+            .collect_vec();
+        let owner = span
+            .iter()
+            .zip(model)
+            .map(|(&(first, last), &word)| {
+                (first..=last)
+                    .rev()
+                    .max_by_key(|&g| overlap(given[g], word))
+                    .unwrap()
+            })
+            .collect_vec();
+        Alignment {
+            cover,
+            main,
+            span,
+            owner,
+        }
+    }
+
+    /// Number of given words.
+    pub(crate) fn len(&self) -> usize {
+        self.main.len()
+    }
+
+    /// Tags of the given words, the tag of their main model word.
+    pub(crate) fn tags<T: Clone>(&self, tags: &[T]) -> Vec<T> {
+        self.main.iter().map(|&m| tags[m].clone()).collect_vec()
+    }
+
+    /// BIO or BIOES `tags` of the model words as tags of the given words, every chunk is
+    /// widened to the given words it overlaps, a chunk overlapping an earlier one is dropped.
+    pub(crate) fn chunks<S: AsRef<str>>(&self, tags: &[S], bioes: bool) -> Vec<String> {
+        let mut projected = vec![String::from("O"); self.main.len()];
+        for (label, start, end) in get_entities(tags.iter().map(|x| x.as_ref()).collect()) {
+            let (start, end) = (self.span[start].0, self.span[end].1);
+            if projected[start..=end].iter().any(|x| x != "O") {
+                continue;
+            }
+            for (idx, tag) in projected[start..=end].iter_mut().enumerate() {
+                let prefix = match (idx == 0, start + idx == end, bioes) {
+                    (true, true, true) => "S",
+                    (true, _, _) => "B",
+                    (false, true, true) => "E",
+                    _ => "I",
+                };
+                *tag = format!("{}-{}", prefix, label);
+            }
+        }
+        projected
+    }
+
+    /// Model word taken as predicate for the given word, the one it mainly covers when the
+    /// given word is also the one mainly covering it.
+    pub(crate) fn predicate(&self, word: usize) -> Option<usize> {
+        let m = self.main[word];
+        match self.owner[m] == word {
+            true => Some(m),
+            false => None,
+        }
+    }
+}
+
+/// Model arcs behind the arcs between the given words of a batch, the highest scoring arc
+/// between their model words, and between the same model word for a word to itself.
+///
+/// Arcs are laid out as `[batch, word, head]` with the root at `0`, as for
+/// [`crate::eisner::eisner`], for the most given words of the batch plus the root.
+pub(crate) struct ArcPairs {
+    size: usize,
+    pairs: Vec<(usize, usize)>,
+}
+
+impl ArcPairs {
+    pub(crate) fn new(scores: &ArrayViewD<f32>, alignments: &[Alignment]) -> ArcPairs {
+        let size = alignments.iter().map(|x| x.main.len()).max().unwrap_or(0) + 1;
+        let mut pairs = vec![(0, 0); alignments.len() * size * size];
+        for (batch, alignment) in alignments.iter().enumerate() {
+            let words = std::iter::once(vec![0])
+                .chain(
+                    alignment
+                        .cover
+                        .iter()
+                        .map(|cover| cover.iter().map(|m| m + 1).collect_vec()),
+                )
+                .collect_vec();
+            for word in 1..words.len() {
+                for head in 0..words.len() {
+                    let best = words[word]
+                        .iter()
+                        .cartesian_product(&words[head])
+                        .filter(|(mw, mh)| word != head || mw == mh)
+                        .max_by(|&(w1, h1), &(w2, h2)| {
+                            scores[[batch, *w1, *h1]]
+                                .partial_cmp(&scores[[batch, *w2, *h2]])
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                        });
+                    if let Some((&mw, &mh)) = best {
+                        pairs[(batch * size + word) * size + head] = (mw, mh);
+                    }
+                }
+            }
+        }
+        ArcPairs { size, pairs }
+    }
+
+    /// `array`, laid out as `[batch, word, head, ...]` for the model words, taken at the model
+    /// arc of every arc between the given words.
+    pub(crate) fn gather<T: Copy>(&self, array: &ArrayViewD<T>) -> ArrayD<T> {
+        let mut shape = array.shape().to_vec();
+        shape[1] = self.size;
+        shape[2] = self.size;
+        let batch_size = shape[0];
+        let mut data = Vec::with_capacity(shape.iter().product());
+        for batch in 0..batch_size {
+            let block = array.index_axis(Axis(0), batch);
+            for cell in 0..self.size * self.size {
+                let (mw, mh) = self.pairs[batch * self.size * self.size + cell];
+                let arc = block.index_axis(Axis(0), mw);
+                data.extend(arc.index_axis(Axis(0), mh).iter().cloned());
+            }
+        }
+        ArrayD::from_shape_vec(IxDyn(&shape), data).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::align::{Alignment, ArcPairs};
+    use onnxruntime::ndarray::{ArrayD, IxDyn};
+
+    #[test]
+    fn test_align() {
+        // "iPhone 12 价格" given as "iPhone", "12", "价格", and read by the model as
+        // "iPhone 12", "价", "格"
+        let given = [(0, 6), (7, 9), (10, 12)];
+        let model = [(0, 9), (10, 11), (11, 12)];
+        let alignment = Alignment::new(&given, &model);
+        assert_eq!(alignment.tags(&["nz", "n", "n"]), vec!["nz", "nz", "n"]);
+        assert_eq!(
+            alignment.chunks(&["S-Nz", "B-Ni", "E-Ni"], true),
+            vec!["B-Nz", "E-Nz", "S-Ni"]
+        );
+        assert_eq!(
+            alignment.chunks(&["B-A0", "O", "B-A1"], false),
+            vec!["B-A0", "I-A0", "B-A1"]
+        );
+        assert_eq!(
+            (0..3).map(|g| alignment.predicate(g)).collect::<Vec<_>>(),
+            vec![Some(0), None, Some(1)]
+        );
+
+        // arcs between the root and the model words: 价 <- 格 <- iPhone 12 <- root
+        let mut scores = ArrayD::from_elem(IxDyn(&[1, 4, 4]), -1.0f32);
+        scores[[0, 1, 0]] = 3.0;
+        scores[[0, 2, 3]] = 2.0;
+        scores[[0, 3, 1]] = 1.0;
+        scores[[0, 1, 1]] = 0.5;
+        let pairs = ArcPairs::new(&scores.view(), &[alignment]);
+        let pooled = pairs.gather(&scores.view());
+        assert_eq!(pooled.shape(), &[1, 4, 4]);
+        // 价格 takes the best arc of its chars, iPhone and 12 share their model word
+        assert_eq!(pooled[[0, 3, 1]], 1.0);
+        assert_eq!(pooled[[0, 3, 2]], 1.0);
+        assert_eq!(pooled[[0, 1, 0]], 3.0);
+        assert_eq!(pooled[[0, 1, 2]], 0.5);
+        assert_eq!(pooled[[0, 3, 3]], -1.0);
+    }
+}
@@ -0,0 +1,94 @@
+use itertools::Itertools;
+
+/// How [`crate::LTP::pipeline_batch_bucketed`] groups sentences into batches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchPolicy {
+    /// max sentences in one batch
+    pub max_batch_size: usize,
+    /// max tokens in one batch once padded to its longest sentence, a sentence longer than
+    /// this still runs alone. Sentences are counted as their chars plus `[CLS]` and `[SEP]`,
+    /// which is never fewer than their tokens.
+    pub max_tokens: Option<usize>,
+}
+
+impl Default for BatchPolicy {
+    fn default() -> Self {
+        BatchPolicy {
+            max_batch_size: 32,
+            max_tokens: None,
+        }
+    }
+}
+
+/// Group the sentences of similar `lengths` together, sentences are sorted by length and cut
+/// into consecutive batches under the limits of `policy`.
+///
+/// Returns the indices of the sentences in every batch.
+pub(crate) fn buckets(lengths: &[usize], policy: BatchPolicy) -> Vec<Vec<usize>> {
+    let order = (0..lengths.len())
+        .sorted_by_key(|&idx| lengths[idx])
+        .collect_vec();
+
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    for idx in order {
+        // sorted by length, so the new sentence is the longest of the batch
+        let padded = (current.len() + 1) * lengths[idx];
+        let full = current.len() >= policy.max_batch_size.max(1)
+            || matches!(policy.max_tokens, Some(max_tokens) if padded > max_tokens);
+        if full && !current.is_empty() {
+            batches.push(std::mem::take(&mut current));
+        }
+        current.push(idx);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    debug_assert!(batches
+        .iter()
+        .flatten()
+        .copied()
+        .sorted()
+        .eq(0..lengths.len()));
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::{buckets, BatchPolicy};
+
+    #[test]
+    fn test_buckets() {
+        let lengths = vec![10, 3, 50, 4, 12, 2];
+        let policy = BatchPolicy {
+            max_batch_size: 2,
+            max_tokens: None,
+        };
+        assert_eq!(
+            buckets(&lengths, policy),
+            vec![vec![5, 1], vec![3, 0], vec![4, 2]]
+        );
+
+        let policy = BatchPolicy {
+            max_batch_size: 8,
+            max_tokens: Some(40),
+        };
+        assert_eq!(
+            buckets(&lengths, policy),
+            vec![vec![5, 1, 3, 0], vec![4], vec![2]]
+        );
+
+        // every sentence lands in exactly one batch, whatever the limits
+        for max_batch_size in 0..8 {
+            for max_tokens in [None, Some(1), Some(20), Some(100)].iter().cloned() {
+                let policy = BatchPolicy {
+                    max_batch_size,
+                    max_tokens,
+                };
+                let mut covered = buckets(&lengths, policy).concat();
+                covered.sort_unstable();
+                assert_eq!(covered, (0..lengths.len()).collect::<Vec<_>>());
+            }
+        }
+    }
+}
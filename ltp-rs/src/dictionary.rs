@@ -0,0 +1,167 @@
+use crate::{LTPError, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// How the dictionary matches are picked when they overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrategy {
+    /// forward maximum matching, the longest word starting at a position wins
+    #[default]
+    Longest,
+    /// the non-overlapping matches with the largest total `weight * length` win
+    Weighted,
+}
+
+/// User lexicon constraining the segmentation, every match in a sentence is forced to be a
+/// single word while the model decides everywhere else.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashMap<String, f32>,
+    max_length: usize,
+    strategy: MatchStrategy,
+}
+
+impl Dictionary {
+    pub fn new(strategy: MatchStrategy) -> Dictionary {
+        Dictionary {
+            words: HashMap::new(),
+            max_length: 0,
+            strategy,
+        }
+    }
+
+    /// Load a plain text lexicon, one word per line with an optional weight separated by
+    /// whitespace, the weight defaults to `1.0`.
+    pub fn load<P: AsRef<Path>>(path: P, strategy: MatchStrategy) -> Result<Dictionary> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+
+        let mut dictionary = Dictionary::new(strategy);
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+            let word = match fields.next() {
+                Some(word) => word,
+                None => continue,
+            };
+            let weight = match fields.next() {
+                Some(weight) => weight.parse().map_err(|_| {
+                    LTPError::InvalidInput(format!("invalid weight at line {}", idx + 1))
+                })?,
+                None => 1.0,
+            };
+            dictionary.add_word(word, weight);
+        }
+        Ok(dictionary)
+    }
+
+    pub fn add_word(&mut self, word: &str, weight: f32) {
+        let length = word.chars().count();
+        if length == 0 {
+            return;
+        }
+        self.max_length = self.max_length.max(length);
+        self.words.insert(word.to_string(), weight);
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn strategy(&self) -> MatchStrategy {
+        self.strategy
+    }
+
+    fn weight(&self, chars: &[char]) -> Option<f32> {
+        let word: String = chars.iter().collect();
+        self.words.get(&word).copied()
+    }
+
+    /// Non-overlapping `[start, end)` char spans of the dictionary words found in `chars`.
+    pub fn matches(&self, chars: &[char]) -> Vec<(usize, usize)> {
+        match self.strategy {
+            MatchStrategy::Longest => self.longest_matches(chars),
+            MatchStrategy::Weighted => self.weighted_matches(chars),
+        }
+    }
+
+    fn longest_matches(&self, chars: &[char]) -> Vec<(usize, usize)> {
+        let mut spans = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let longest = (1..=self.max_length.min(chars.len() - start))
+                .rev()
+                .find(|&length| self.weight(&chars[start..start + length]).is_some());
+            match longest {
+                Some(length) => {
+                    spans.push((start, start + length));
+                    start += length;
+                }
+                None => start += 1,
+            }
+        }
+        spans
+    }
+
+    fn weighted_matches(&self, chars: &[char]) -> Vec<(usize, usize)> {
+        // best[i]: best score of chars[..i], back[i]: start of the match ending at i
+        let mut best = vec![0f32; chars.len() + 1];
+        let mut back: Vec<Option<usize>> = vec![None; chars.len() + 1];
+        for end in 1..=chars.len() {
+            best[end] = best[end - 1];
+            for length in 1..=self.max_length.min(end) {
+                let start = end - length;
+                if let Some(weight) = self.weight(&chars[start..end]) {
+                    let score = best[start] + weight * length as f32;
+                    if weight > 0.0 && score > best[end] {
+                        best[end] = score;
+                        back[end] = Some(start);
+                    }
+                }
+            }
+        }
+
+        let mut spans = Vec::new();
+        let mut end = chars.len();
+        while end > 0 {
+            match back[end] {
+                Some(start) => {
+                    spans.push((start, end));
+                    end = start;
+                }
+                None => end -= 1,
+            }
+        }
+        spans.reverse();
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dictionary::{Dictionary, MatchStrategy};
+
+    #[test]
+    fn test_dictionary() {
+        let chars: Vec<char> = "他叫汤姆去拿外衣".chars().collect();
+
+        let mut dictionary = Dictionary::new(MatchStrategy::Longest);
+        dictionary.add_word("汤姆", 1.0);
+        dictionary.add_word("汤姆去", 1.0);
+        dictionary.add_word("拿外", 1.0);
+        let matches = dictionary.matches(&chars);
+        assert_eq!(matches, vec![(2, 5), (5, 7)]);
+
+        let mut dictionary = Dictionary::new(MatchStrategy::Weighted);
+        dictionary.add_word("汤姆", 2.0);
+        dictionary.add_word("姆去拿", 1.0);
+        dictionary.add_word("拿外衣", 1.0);
+        assert_eq!(dictionary.matches(&chars), vec![(2, 4), (5, 8)]);
+    }
+}
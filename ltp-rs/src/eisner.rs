@@ -42,7 +42,33 @@ fn backtrack(
     }
 }
 
+/// How many words may be attached to the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RootPolicy {
+    /// Exactly one word is attached to the root
+    #[default]
+    Single,
+    /// Any number of words are attached to the root, for fragmented text
+    Multiple,
+}
+
+/// Projective decoding of dependency trees with exactly one word attached to the root, see
+/// [`eisner_with_root`].
 pub fn eisner<T>(scores: &[T], stn_length: &[usize], remove_root: bool) -> Vec<Vec<usize>>
+where
+    T: Float + Display,
+{
+    eisner_with_root(scores, stn_length, remove_root, RootPolicy::Single)
+}
+
+/// Projective decoding of dependency trees, `scores` is `[b, w, n]`, the score of the arc from
+/// head `n` to word `w`, where the root is word `0` and counted in `stn_length`.
+pub fn eisner_with_root<T>(
+    scores: &[T],
+    stn_length: &[usize],
+    remove_root: bool,
+    root: RootPolicy,
+) -> Vec<Vec<usize>>
 where
     T: Float + Display,
 {
@@ -141,8 +167,11 @@ where
                 bs_c[i * max_stn_len_use + j] = max_score;
                 bp_c[i * max_stn_len_use + j] = max_index;
             }
-            if stn_length[b] != w {
-                bs_c[0 * max_stn_len_use + w] = T::neg_infinity();
+            // with a single root, the only complete span of the root is the whole sentence, so
+            // its incomplete spans are built on its empty span alone: the root takes exactly
+            // one child, which heads every other word
+            if root == RootPolicy::Single && w != max_stn_len_use - 1 {
+                bs_c[w] = T::neg_infinity();
             }
         }
         let mut b_head = vec![1usize; max_stn_len_use - remove_root];
@@ -161,12 +190,134 @@ where
     res
 }
 
+fn log_add<T: Float>(a: T, b: T) -> T {
+    let max = a.max(b);
+    if max == T::neg_infinity() {
+        return max;
+    }
+    max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+/// Log-space inside-outside over the projective trees with a single root attachment, the sum
+/// counterpart of [`eisner`].
+///
+/// Same layout as [`eisner`]: `scores` is `[b, w, n]`, the score of the arc from head `n` to word
+/// `w`, where the root is word `0` and counted in `stn_length`. Returns the marginal probability
+/// of every arc in that layout, `0` for padding and for arcs into the root, and the log partition
+/// function of every sentence.
+pub fn eisner_inside_outside<T>(scores: &[T], stn_length: &[usize]) -> (Vec<T>, Vec<T>)
+where
+    T: Float,
+{
+    let max_stn_len = *stn_length.iter().max().unwrap();
+    let score_block_size = max_stn_len * max_stn_len;
+
+    let mut marginals = vec![T::zero(); scores.len()];
+    let mut log_partitions = Vec::with_capacity(stn_length.len());
+    for (b, &n) in stn_length.iter().enumerate() {
+        let bscore_bias = b * score_block_size;
+        let arc = |head: usize, dep: usize| scores[bscore_bias + dep * max_stn_len + head];
+
+        // [h, e], span between head `h` and its other end `e`
+        let mut in_i = vec![T::neg_infinity(); n * n];
+        let mut in_c = vec![T::neg_infinity(); n * n];
+        for k in 0..n {
+            in_c[k * n + k] = T::zero();
+        }
+        for w in 1..n {
+            for i in 0..n - w {
+                let j = i + w;
+                let (mut left, mut right) = (T::neg_infinity(), T::neg_infinity());
+                for r in i..j {
+                    let s = in_c[i * n + r] + in_c[j * n + r + 1];
+                    left = log_add(left, s + arc(j, i));
+                    right = log_add(right, s + arc(i, j));
+                }
+                in_i[j * n + i] = left;
+                in_i[i * n + j] = right;
+            }
+            for i in 0..n - w {
+                let j = i + w;
+                let (mut left, mut right) = (T::neg_infinity(), T::neg_infinity());
+                for r in i..j {
+                    left = log_add(left, in_c[r * n + i] + in_i[j * n + r]);
+                }
+                for r in i + 1..j + 1 {
+                    right = log_add(right, in_i[i * n + r] + in_c[r * n + j]);
+                }
+                in_c[j * n + i] = left;
+                in_c[i * n + j] = right;
+            }
+            // only the whole sentence may be a complete span of the root, which then has
+            // exactly one child
+            if w != n - 1 {
+                in_c[w] = T::neg_infinity();
+            }
+        }
+
+        let log_partition = match n {
+            0 => T::zero(),
+            _ => in_c[n - 1],
+        };
+        log_partitions.push(log_partition);
+        if n < 2 || log_partition == T::neg_infinity() {
+            continue;
+        }
+
+        let mut out_i = vec![T::neg_infinity(); n * n];
+        let mut out_c = vec![T::neg_infinity(); n * n];
+        out_c[n - 1] = T::zero();
+        for w in (1..n).rev() {
+            for i in 0..n - w {
+                let j = i + w;
+                // C(j->i) = C(r->i) + I(j->r)
+                let out = out_c[j * n + i];
+                for r in i..j {
+                    out_c[r * n + i] = log_add(out_c[r * n + i], out + in_i[j * n + r]);
+                    out_i[j * n + r] = log_add(out_i[j * n + r], out + in_c[r * n + i]);
+                }
+                // C(i->j) = I(i->r) + C(r->j)
+                let out = out_c[i * n + j];
+                for r in i + 1..j + 1 {
+                    out_i[i * n + r] = log_add(out_i[i * n + r], out + in_c[r * n + j]);
+                    out_c[r * n + j] = log_add(out_c[r * n + j], out + in_i[i * n + r]);
+                }
+            }
+            for i in 0..n - w {
+                let j = i + w;
+                // I(j->i) and I(i->j) = C(i->r) + C(j->r+1) + s
+                let (left, right) = (out_i[j * n + i], out_i[i * n + j]);
+                for r in i..j {
+                    let out = log_add(left + arc(j, i), right + arc(i, j));
+                    out_c[i * n + r] = log_add(out_c[i * n + r], out + in_c[j * n + r + 1]);
+                    out_c[j * n + r + 1] = log_add(out_c[j * n + r + 1], out + in_c[i * n + r]);
+                }
+            }
+            // the spans of the root masked above take part in no tree
+            if w > 1 {
+                out_c[w - 1] = T::neg_infinity();
+            }
+        }
+
+        for dep in 1..n {
+            for head in 0..n {
+                if head != dep {
+                    let idx = bscore_bias + dep * max_stn_len + head;
+                    let log_marginal = in_i[head * n + dep] + out_i[head * n + dep];
+                    marginals[idx] = (log_marginal - log_partition).exp();
+                }
+            }
+        }
+    }
+    (marginals, log_partitions)
+}
+
 #[cfg(test)]
 mod tests {
     use ndarray_npy::{NpzReader, ReadNpzError};
     use std::fs::File;
 
-    use crate::eisner::eisner;
+    use crate::eisner::{eisner, eisner_inside_outside, eisner_with_root, RootPolicy};
     use ndarray::{Array1, Array3};
 
     #[test]
@@ -186,4 +337,76 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_eisner_root() {
+        // root + 3 words, the root prefers both 1 and 3
+        let n = 4;
+        let mut scores = vec![-10.0f32; n * n];
+        let mut arc = |head: usize, dep: usize, score: f32| scores[dep * n + head] = score;
+        arc(0, 1, 5.0);
+        arc(0, 3, 5.0);
+        arc(1, 2, 3.0);
+        arc(1, 3, 2.0);
+
+        let single = eisner_with_root(&scores, &[4], true, RootPolicy::Single);
+        assert_eq!(single, vec![vec![0, 1, 1]]);
+        assert_eq!(single, eisner(&scores, &[4], true));
+        let multiple = eisner_with_root(&scores, &[4], true, RootPolicy::Multiple);
+        assert_eq!(multiple, vec![vec![0, 1, 0]]);
+    }
+
+    #[test]
+    fn test_eisner_inside_outside() {
+        // root + 4 words, and root + 2 words padded to the same size
+        let n = 5;
+        let scores = (0..2 * n * n)
+            .map(|x| ((x * 7 % 11) as f64 - 5.0) / 3.0)
+            .collect::<Vec<_>>();
+        let (marginals, log_partitions) = eisner_inside_outside(&scores, &[5, 3]);
+
+        // brute force over all projective trees with a single root attachment
+        for (b, &len) in [5usize, 3].iter().enumerate() {
+            let score = |dep: usize, head: usize| scores[b * n * n + dep * n + head];
+            let mut partition = 0.0;
+            let mut expected = vec![0.0; n * n];
+            let mut heads = vec![0; len];
+            let trees = len.pow(len as u32 - 1);
+            for code in 0..trees {
+                let mut rest = code;
+                for head in heads.iter_mut().skip(1) {
+                    *head = rest % len;
+                    rest /= len;
+                }
+                let ancestor = |mut node: usize, head: usize| {
+                    for _ in 0..len {
+                        if node == head {
+                            return true;
+                        }
+                        node = heads[node];
+                    }
+                    false
+                };
+                let roots = heads.iter().skip(1).filter(|&&h| h == 0).count();
+                let valid = (1..len).all(|dep| {
+                    let (head, lo, hi) = (heads[dep], dep.min(heads[dep]), dep.max(heads[dep]));
+                    head != dep
+                        && ancestor(dep, 0)
+                        && (lo + 1..hi).all(|between| ancestor(between, head))
+                });
+                if roots == 1 && valid {
+                    let weight = (1..len)
+                        .map(|dep| score(dep, heads[dep]))
+                        .sum::<f64>()
+                        .exp();
+                    partition += weight;
+                    (1..len).for_each(|dep| expected[dep * n + heads[dep]] += weight);
+                }
+            }
+            assert!((log_partitions[b] - partition.ln()).abs() < 1e-9);
+            for (idx, &x) in expected.iter().enumerate() {
+                assert!((marginals[b * n * n + idx] - x / partition).abs() < 1e-9);
+            }
+        }
+    }
 }
@@ -64,6 +64,39 @@ fn end_of_chunk(prev_tag: &str, tag: &str, prev_type: &str, type_: &str) -> bool
     false
 }
 
+fn split_tag(chunk: &str) -> (&str, &str) {
+    match chunk.find('-') {
+        None => (chunk, "_"),
+        Some(cut) => (&chunk[..cut], &chunk[cut + 1..]),
+    }
+}
+
+/// Allowed transitions between `labels` in BIO or BIOES tagging, flattened as `[from, to]`.
+///
+/// `I` and `E` only continue a `B` or `I` of the same type, in BIOES (when any `E` or `S`
+/// label exists) a `B` or `I` must also be continued.
+pub fn allowed_transitions<S: AsRef<str>>(labels: &[S]) -> Vec<bool> {
+    let tags = labels
+        .iter()
+        .map(|x| split_tag(x.as_ref()))
+        .collect::<Vec<_>>();
+    let bioes = tags.iter().any(|&(tag, _type)| tag == "E" || tag == "S");
+
+    let mut allowed = Vec::with_capacity(tags.len() * tags.len());
+    for &(prev_tag, prev_type) in &tags {
+        for &(tag, type_) in &tags {
+            let opened = prev_tag == "B" || prev_tag == "I";
+            let continued = (tag == "I" || tag == "E") && prev_type == type_;
+            allowed.push(match (opened, continued) {
+                (true, true) => true,
+                (true, false) => !bioes && tag != "I" && tag != "E",
+                (false, _) => tag != "I" && tag != "E",
+            });
+        }
+    }
+    allowed
+}
+
 pub fn get_entities(mut seq: Vec<&str>) -> Vec<(&str, usize, usize)> {
     seq.push("O");
 
@@ -73,11 +106,7 @@ pub fn get_entities(mut seq: Vec<&str>) -> Vec<(&str, usize, usize)> {
     let mut chunks: Vec<(&str, usize, usize)> = Vec::new();
 
     for (i, chunk) in seq.iter().enumerate() {
-        let cut = chunk.find('-');
-        let (tag, type_) = match cut {
-            None => (&chunk[0..], "_"),
-            Some(cut) => (&chunk[..cut], &chunk[cut + 1..]),
-        };
+        let (tag, type_) = split_tag(chunk);
         if end_of_chunk(prev_tag, tag, prev_type, type_) {
             chunks.push((prev_type.clone(), begin_offset, i - 1));
         }
@@ -93,12 +122,33 @@ pub fn get_entities(mut seq: Vec<&str>) -> Vec<(&str, usize, usize)> {
 
 #[cfg(test)]
 mod tests {
-    use crate::entities::get_entities;
+    use crate::entities::{allowed_transitions, get_entities};
 
     #[test]
     fn test_get_entities() {
         let example = vec!["B-PER", "I-PER", "O", "B-LOC"];
         let result = get_entities(example);
         assert_eq!(result, vec![("PER", 0, 1), ("LOC", 3, 3)]);
+
+        let allowed = allowed_transitions(&["O", "B-PER", "I-PER", "B-LOC"]);
+        assert_eq!(
+            allowed,
+            vec![
+                true, true, false, true, // O ->
+                true, true, true, true, // B-PER ->
+                true, true, true, true, // I-PER ->
+                true, true, false, true, // B-LOC ->
+            ]
+        );
+        let allowed = allowed_transitions(&["B", "I", "E", "S"]);
+        assert_eq!(
+            allowed,
+            vec![
+                false, true, true, false, // B ->
+                false, true, true, false, // I ->
+                true, false, false, true, // E ->
+                true, false, false, true, // S ->
+            ]
+        );
     }
 }
@@ -23,6 +23,18 @@ pub enum LTPError {
 
     #[error("{0}")]
     ShapeError(ndarray::ShapeError),
+
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("the service queue is full")]
+    Overloaded,
+
+    #[error("the service is shut down")]
+    ServiceClosed,
+
+    #[error("{0}")]
+    ServiceError(String),
 }
 
 impl From<onnxruntime::OrtError> for LTPError {
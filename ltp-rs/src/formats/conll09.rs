@@ -0,0 +1,334 @@
+//! [CoNLL-2009](https://ufal.mff.cuni.cz/conll2009-st/task-description.html), one word per line
+//! with the columns `ID FORM LEMMA PLEMMA POS PPOS FEAT PFEAT HEAD PHEAD DEPREL PDEPREL FILLPRED
+//! PRED APRED1..n`, one `APRED` column per predicate of the sentence.
+//!
+//! CoNLL-2009 marks the head word of every argument instead of its span, the head of a span is
+//! found through the dependency tree when there is one, and the span of a head is its subtree.
+
+use std::io::{BufRead, Write};
+
+use itertools::Itertools;
+
+use crate::formats::{column, word_column};
+use crate::{LTPError, LTPResult, Result, SrlArg, SrlFrame, DEP};
+
+/// Head word of the words `start..=end`, the first one whose head is out of the span, or the
+/// last word without a dependency tree or without an arc for the words.
+pub fn span_head(dep: Option<&[DEP]>, start: usize, end: usize) -> usize {
+    let dep = match dep {
+        Some(dep) if end < dep.len() => dep,
+        _ => return end,
+    };
+    (start..=end)
+        .find(|&word| {
+            // heads are counted from 1, 0 being the root
+            let head = dep[word].arc;
+            head == 0 || head - 1 < start || head - 1 > end
+        })
+        .unwrap_or(end)
+}
+
+/// Words `start..=end` covered by the subtree of `head`, cut at `predicate` when it falls inside
+/// it, or `head` alone without a dependency tree or without an arc for `head`.
+pub fn head_span(dep: Option<&[DEP]>, head: usize, predicate: usize) -> (usize, usize) {
+    let dep = match dep {
+        Some(dep) if head < dep.len() => dep,
+        _ => return (head, head),
+    };
+    let mut subtree = vec![false; dep.len()];
+    subtree[head] = true;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for word in 0..dep.len() {
+            let parent = dep[word].arc;
+            if !subtree[word] && parent != 0 && subtree.get(parent - 1) == Some(&true) {
+                subtree[word] = true;
+                changed = true;
+            }
+        }
+    }
+
+    let (mut start, mut end) = (head, head);
+    while start > 0 && subtree[start - 1] && start - 1 != predicate {
+        start -= 1;
+    }
+    while end + 1 < dep.len() && subtree[end + 1] && end + 1 != predicate {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Write `results` as CoNLL-2009, one sentence each, the POS tags and the dependency tree fill
+/// both the gold and the predicted columns.
+pub fn write<W: Write>(writer: &mut W, results: &[LTPResult]) -> std::io::Result<()> {
+    for result in results {
+        let word_num = result.word_num();
+        let dep = result.dep.as_deref();
+        let frames = result.srl.as_deref().unwrap_or_default();
+        // roles of the argument heads of every frame
+        let mut apreds = vec![vec!["_"; frames.len()]; word_num];
+        for (idx, frame) in frames.iter().enumerate() {
+            // arguments past the words are skipped
+            for arg in frame.arguments.iter().filter(|x| x.start <= x.end) {
+                if let Some(apred) = apreds.get_mut(span_head(dep, arg.start, arg.end)) {
+                    apred[idx] = arg.role.as_str();
+                }
+            }
+        }
+
+        for idx in 0..word_num {
+            let form = word_column(result.seg.as_ref(), idx);
+            let pos = word_column(result.pos.as_ref(), idx);
+            let (head, deprel) = match dep.and_then(|x| x.get(idx)) {
+                Some(dep) => (dep.arc.to_string(), dep.rel.as_str()),
+                None => (String::from("_"), "_"),
+            };
+            let (fillpred, pred) = match frames.iter().any(|x| x.predicate == idx) {
+                true => ("Y", form),
+                false => ("_", "_"),
+            };
+
+            write!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}\t{}\t_\t_\t{}\t{}\t{}\t{}\t{}\t{}",
+                idx + 1,
+                form,
+                form,
+                form,
+                pos,
+                pos,
+                head,
+                head,
+                deprel,
+                deprel,
+                fillpred,
+                pred
+            )?;
+            for role in &apreds[idx] {
+                write!(writer, "\t{}", role)?;
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// `results` as a CoNLL-2009 document.
+pub fn to_string(results: &[LTPResult]) -> String {
+    let mut buffer = Vec::new();
+    write(&mut buffer, results).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+struct Token<'a> {
+    form: &'a str,
+    pos: Option<&'a str>,
+    head: Option<usize>,
+    deprel: Option<&'a str>,
+    predicate: bool,
+    apreds: Vec<Option<&'a str>>,
+}
+
+fn invalid(line_no: usize, message: &str) -> LTPError {
+    LTPError::InvalidInput(format!("CoNLL-2009 line {}: {}", line_no + 1, message))
+}
+
+fn parse_token(line: &str, line_no: usize) -> Result<Token<'_>> {
+    let columns = line.split('\t').collect_vec();
+    if columns.len() < 14 {
+        return Err(invalid(
+            line_no,
+            "expected at least 14 tab separated columns",
+        ));
+    }
+    let number = |x: &str| {
+        x.parse::<usize>()
+            .map_err(|_| invalid(line_no, "bad index"))
+    };
+
+    // the gold columns first, the predicted ones otherwise
+    Ok(Token {
+        form: columns[1],
+        pos: column(columns[4]).or_else(|| column(columns[5])),
+        head: column(columns[8])
+            .or_else(|| column(columns[9]))
+            .map(number)
+            .transpose()?,
+        deprel: column(columns[10]).or_else(|| column(columns[11])),
+        predicate: columns[12] == "Y"
+            || (column(columns[12]).is_none() && column(columns[13]).is_some()),
+        apreds: columns[14..].iter().map(|&x| column(x)).collect(),
+    })
+}
+
+fn to_result(tokens: &[Token], line_no: usize) -> Result<LTPResult> {
+    let mut result = LTPResult {
+        seg: Some(tokens.iter().map(|x| x.form.to_string()).collect()),
+        ..LTPResult::default()
+    };
+    if tokens.iter().all(|x| x.pos.is_some()) {
+        result.pos = Some(tokens.iter().map(|x| x.pos.unwrap().to_string()).collect());
+    }
+    if tokens
+        .iter()
+        .all(|x| x.head.is_some() && x.deprel.is_some())
+    {
+        if tokens.iter().any(|x| x.head.unwrap() > tokens.len()) {
+            return Err(invalid(line_no, "HEAD out of the sentence"));
+        }
+        let dep = tokens.iter().map(|x| DEP {
+            arc: x.head.unwrap(),
+            rel: x.deprel.unwrap().to_string(),
+        });
+        result.dep = Some(dep.collect());
+    }
+
+    let predicates = tokens.iter().positions(|x| x.predicate).collect_vec();
+    if tokens.iter().any(|x| x.apreds.len() != predicates.len()) {
+        return Err(invalid(line_no, "expected one APRED column per predicate"));
+    }
+    let dep = result.dep.as_deref();
+    let frames = predicates.iter().enumerate().map(|(idx, &predicate)| {
+        let arguments = tokens
+            .iter()
+            .enumerate()
+            .filter_map(|(head, x)| x.apreds[idx].map(|role| (head, role)))
+            .map(|(head, role)| {
+                let (start, end) = head_span(dep, head, predicate);
+                SrlArg {
+                    role: role.to_string(),
+                    start,
+                    end,
+                }
+            });
+        SrlFrame {
+            predicate,
+            arguments: arguments.collect(),
+        }
+    });
+    result.srl = Some(frames.collect());
+    Ok(result)
+}
+
+/// Read the sentences of a CoNLL-2009 document, the spans of the arguments are the subtrees of
+/// their heads, see [`head_span`].
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<LTPResult>> {
+    let lines = reader.lines().collect::<std::io::Result<Vec<_>>>()?;
+    let mut results = Vec::new();
+    let mut tokens = Vec::new();
+    for (line_no, line) in lines.iter().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            if !tokens.is_empty() {
+                results.push(to_result(&tokens, line_no)?);
+                tokens.clear();
+            }
+        } else if !line.starts_with('#') {
+            tokens.push(parse_token(line, line_no)?);
+        }
+    }
+    if !tokens.is_empty() {
+        results.push(to_result(&tokens, lines.len())?);
+    }
+    Ok(results)
+}
+
+/// Parse a CoNLL-2009 document, see [`read`].
+pub fn parse(text: &str) -> Result<Vec<LTPResult>> {
+    read(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::conll09;
+    use crate::{LTPResult, SrlArg, SrlFrame, DEP};
+
+    #[test]
+    fn test_conll09() {
+        let dep = |arc: usize, rel: &str| DEP {
+            arc,
+            rel: rel.to_string(),
+        };
+        let arg = |role: &str, start: usize, end: usize| SrlArg {
+            role: role.to_string(),
+            start,
+            end,
+        };
+        // 他 的 哥哥 昨天 走 了
+        let result = LTPResult {
+            seg: Some(vec![
+                "他".into(),
+                "的".into(),
+                "哥哥".into(),
+                "昨天".into(),
+                "走".into(),
+                "了".into(),
+            ]),
+            pos: Some(vec![
+                "r".into(),
+                "u".into(),
+                "n".into(),
+                "nt".into(),
+                "v".into(),
+                "u".into(),
+            ]),
+            dep: Some(vec![
+                dep(3, "ATT"),
+                dep(1, "RAD"),
+                dep(5, "SBV"),
+                dep(5, "ADV"),
+                dep(0, "HED"),
+                dep(5, "RAD"),
+            ]),
+            srl: Some(vec![SrlFrame {
+                predicate: 4,
+                arguments: vec![arg("A0", 0, 2), arg("ARGM-TMP", 3, 3)],
+            }]),
+            ..LTPResult::default()
+        };
+        assert_eq!(conll09::span_head(result.dep.as_deref(), 0, 2), 2);
+        assert_eq!(conll09::span_head(None, 0, 2), 2);
+
+        let text = conll09::to_string(std::slice::from_ref(&result));
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[2],
+            "3\t哥哥\t哥哥\t哥哥\tn\tn\t_\t_\t5\t5\tSBV\tSBV\t_\t_\tA0"
+        );
+        assert_eq!(
+            lines[4],
+            "5\t走\t走\t走\tv\tv\t_\t_\t0\t0\tHED\tHED\tY\t走\t_"
+        );
+        let parsed = conll09::parse(&text).unwrap();
+        assert_eq!(
+            serde_json::to_string(&parsed).unwrap(),
+            serde_json::to_string(&[result]).unwrap()
+        );
+
+        // the subtree of an argument head is cut at the predicate
+        let text = "1\t想\t_\t_\tv\t_\t_\t_\t0\t_\tHED\t_\t_\t_\tA0\n\
+            2\t去\t_\t_\tv\t_\t_\t_\t1\t_\tVOB\t_\tY\t去\t_\n\
+            3\t北京\t_\t_\tns\t_\t_\t_\t2\t_\tVOB\t_\t_\t_\tA1\n";
+        let parsed = conll09::parse(text).unwrap();
+        let frames = parsed[0].srl.as_ref().unwrap();
+        assert_eq!(frames[0].arguments, vec![arg("A0", 0, 0), arg("A1", 2, 2)]);
+        assert!(conll09::parse("1\t想\t_\t_\tv\t_\t_\t_\t0\t_\tHED\t_\t_\t_\n2\t去\n").is_err());
+
+        // arcs and arguments past the words of a hand built result are skipped
+        let result = LTPResult {
+            seg: Some(vec!["想".into(), "去".into()]),
+            dep: Some(vec![dep(7, "VOB")]),
+            srl: Some(vec![SrlFrame {
+                predicate: 1,
+                arguments: vec![arg("A1", 0, 4), arg("A0", 0, 0)],
+            }]),
+            ..LTPResult::default()
+        };
+        let text = conll09::to_string(&[result]);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "1	想	想	想	_	_	_	_	7	7	VOB	VOB	_	_	A0");
+        assert_eq!(lines[1], "2	去	去	去	_	_	_	_	_	_	_	_	Y	去	_");
+    }
+}
@@ -0,0 +1,379 @@
+//! [CoNLL-U](https://universaldependencies.org/format.html), one word per line with the
+//! columns `ID FORM LEMMA UPOS XPOS FEATS HEAD DEPREL DEPS MISC`.
+//!
+//! POS tags are written to XPOS and mapped to UPOS, the dependency tree to HEAD and DEPREL,
+//! the semantic graph to DEPS, and the NER tags and char offsets to `NER` and `TokenRange` in
+//! MISC.
+
+use std::io::{BufRead, Write};
+
+use itertools::Itertools;
+
+use crate::formats::{column, semantic_heads, sentence_text, word_column};
+use crate::{Entity, LTPError, LTPResult, Result, DEP, SDP};
+
+/// Universal POS tag of an LTP POS tag.
+pub fn upos(tag: &str) -> &'static str {
+    match tag {
+        "a" | "b" | "z" => "ADJ",
+        "c" => "CCONJ",
+        "d" => "ADV",
+        "e" => "INTJ",
+        "h" | "k" | "u" => "PART",
+        "m" => "NUM",
+        "n" | "nd" | "nl" | "nt" | "q" => "NOUN",
+        "nh" | "ni" | "ns" | "nz" => "PROPN",
+        "p" => "ADP",
+        "r" => "PRON",
+        "v" => "VERB",
+        "wp" => "PUNCT",
+        _ => "X",
+    }
+}
+
+/// Write `results` as CoNLL-U, one sentence each.
+pub fn write<W: Write>(writer: &mut W, results: &[LTPResult]) -> std::io::Result<()> {
+    for result in results {
+        if let Some(text) = sentence_text(result) {
+            writeln!(writer, "# text = {}", text)?;
+        }
+        let word_num = result.word_num();
+        let heads = semantic_heads(result, word_num);
+
+        for idx in 0..word_num {
+            let form = word_column(result.seg.as_ref(), idx);
+            let xpos = word_column(result.pos.as_ref(), idx);
+            let upos = match xpos {
+                "_" => "_",
+                xpos => upos(xpos),
+            };
+            let (head, deprel) = match result.dep.as_ref().and_then(|x| x.get(idx)) {
+                Some(dep) => (dep.arc.to_string(), dep.rel.as_str()),
+                None => (String::from("_"), "_"),
+            };
+            let deps = match heads[idx + 1].is_empty() {
+                true => String::from("_"),
+                false => heads[idx + 1]
+                    .iter()
+                    .sorted()
+                    .map(|(head, rel)| format!("{}:{}", head, rel))
+                    .join("|"),
+            };
+
+            let mut misc = Vec::new();
+            if let Some(ner) = result.ner.as_ref().and_then(|x| x.get(idx)) {
+                misc.push(format!("NER={}", ner));
+            }
+            let offsets = result.char_offsets.as_ref();
+            let space_after = match offsets.and_then(|x| x.get(idx)) {
+                Some(&(start, end)) => {
+                    misc.push(format!("TokenRange={}:{}", start, end));
+                    offsets.unwrap().get(idx + 1).map(|next| next.0 > end)
+                }
+                None => Some(false).filter(|_| idx + 1 < word_num),
+            };
+            if space_after == Some(false) {
+                misc.push(String::from("SpaceAfter=No"));
+            }
+            let misc = match misc.is_empty() {
+                true => String::from("_"),
+                false => misc.join("|"),
+            };
+
+            writeln!(
+                writer,
+                "{}\t{}\t_\t{}\t{}\t_\t{}\t{}\t{}\t{}",
+                idx + 1,
+                form,
+                upos,
+                xpos,
+                head,
+                deprel,
+                deps,
+                misc
+            )?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// `results` as a CoNLL-U document.
+pub fn to_string(results: &[LTPResult]) -> String {
+    let mut buffer = Vec::new();
+    write(&mut buffer, results).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+struct Token<'a> {
+    form: &'a str,
+    upos: Option<&'a str>,
+    xpos: Option<&'a str>,
+    head: Option<usize>,
+    deprel: Option<&'a str>,
+    deps: Vec<(usize, &'a str)>,
+    ner: Option<&'a str>,
+    range: Option<(usize, usize)>,
+    space_after: bool,
+}
+
+fn invalid(line_no: usize, message: &str) -> LTPError {
+    LTPError::InvalidInput(format!("CoNLL-U line {}: {}", line_no + 1, message))
+}
+
+fn parse_token(line: &str, line_no: usize) -> Result<Option<Token<'_>>> {
+    let columns = line.split('\t').collect_vec();
+    if columns.len() != 10 {
+        return Err(invalid(line_no, "expected 10 tab separated columns"));
+    }
+    // multiword tokens and empty nodes are not words of their own
+    if columns[0].contains('-') || columns[0].contains('.') {
+        return Ok(None);
+    }
+
+    let number = |x: &str| {
+        x.parse::<usize>()
+            .map_err(|_| invalid(line_no, "bad index"))
+    };
+    let head = column(columns[6]).map(number).transpose()?;
+    let mut deps = Vec::new();
+    for dep in column(columns[8]).into_iter().flat_map(|x| x.split('|')) {
+        let (head, rel) = dep
+            .split_once(':')
+            .ok_or_else(|| invalid(line_no, "bad DEPS"))?;
+        // edges to empty nodes have no word to point to
+        if !head.contains('.') {
+            deps.push((number(head)?, rel));
+        }
+    }
+
+    let mut token = Token {
+        form: columns[1],
+        upos: column(columns[3]),
+        xpos: column(columns[4]),
+        head,
+        deprel: column(columns[7]),
+        deps,
+        ner: None,
+        range: None,
+        space_after: true,
+    };
+    for item in column(columns[9]).into_iter().flat_map(|x| x.split('|')) {
+        match item.split_once('=') {
+            Some(("NER", tag)) => token.ner = Some(tag),
+            Some(("TokenRange", range)) => {
+                let (start, end) = range
+                    .split_once(':')
+                    .ok_or_else(|| invalid(line_no, "bad TokenRange"))?;
+                let (start, end) = (number(start)?, number(end)?);
+                if start > end {
+                    return Err(invalid(line_no, "bad TokenRange"));
+                }
+                token.range = Some((start, end));
+            }
+            Some(("SpaceAfter", "No")) => token.space_after = false,
+            _ => {}
+        }
+    }
+    Ok(Some(token))
+}
+
+fn to_result(tokens: &[Token]) -> LTPResult {
+    let seg = tokens.iter().map(|x| x.form.to_string()).collect_vec();
+    // ranges are only kept when they agree with the words
+    let ranges = tokens.iter().scan(0, |last, x| {
+        let (start, end) = x.range?;
+        let valid = start >= *last && end - start == x.form.chars().count();
+        *last = end;
+        Some(valid)
+    });
+    let char_offsets = match ranges.filter(|&x| x).count() == tokens.len() {
+        true => tokens.iter().map(|x| x.range.unwrap()).collect_vec(),
+        false => tokens
+            .iter()
+            .scan(0, |start, x| {
+                let end = *start + x.form.chars().count();
+                let span = (*start, end);
+                *start = end + x.space_after as usize;
+                Some(span)
+            })
+            .collect_vec(),
+    };
+    let mut result = LTPResult {
+        seg: Some(seg),
+        char_offsets: Some(char_offsets),
+        ..LTPResult::default()
+    };
+
+    let text = sentence_text(&result).unwrap_or_default();
+    let bytes = text
+        .char_indices()
+        .map(|(byte, _char)| byte)
+        .chain(std::iter::once(text.len()))
+        .collect_vec();
+    let char_offsets = result.char_offsets.as_ref().unwrap();
+    result.byte_offsets = Some(
+        char_offsets
+            .iter()
+            .map(|&(start, end)| (bytes[start], bytes[end]))
+            .collect_vec(),
+    );
+
+    if tokens.iter().all(|x| x.xpos.or(x.upos).is_some()) {
+        let pos = tokens
+            .iter()
+            .map(|x| x.xpos.or(x.upos).unwrap().to_string());
+        result.pos = Some(pos.collect_vec());
+    }
+    if tokens.iter().any(|x| x.ner.is_some()) {
+        let ner = tokens
+            .iter()
+            .map(|x| x.ner.unwrap_or("O").to_string())
+            .collect_vec();
+        let chars = text.chars().collect_vec();
+        result.entities = Some(Entity::from_tags(&ner, char_offsets, &chars));
+        result.ner = Some(ner);
+    }
+    if tokens
+        .iter()
+        .all(|x| x.head.is_some() && x.deprel.is_some())
+    {
+        let dep = tokens.iter().map(|x| DEP {
+            arc: x.head.unwrap(),
+            rel: x.deprel.unwrap().to_string(),
+        });
+        result.dep = Some(dep.collect_vec());
+    }
+    if tokens.iter().any(|x| !x.deps.is_empty()) {
+        let sdp = tokens.iter().enumerate().flat_map(|(idx, x)| {
+            x.deps.iter().map(move |&(head, rel)| SDP {
+                src: idx + 1,
+                tgt: head,
+                rel: rel.to_string(),
+            })
+        });
+        result.sdp = Some(sdp.collect_vec());
+    }
+    result
+}
+
+/// Read the sentences of a CoNLL-U document, multiword tokens and empty nodes are skipped.
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<LTPResult>> {
+    let lines = reader.lines().collect::<std::io::Result<Vec<_>>>()?;
+    let mut results = Vec::new();
+    let mut tokens = Vec::new();
+    for (line_no, line) in lines.iter().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            if !tokens.is_empty() {
+                results.push(to_result(&tokens));
+                tokens.clear();
+            }
+        } else if !line.starts_with('#') {
+            tokens.extend(parse_token(line, line_no)?);
+        }
+    }
+    if !tokens.is_empty() {
+        results.push(to_result(&tokens));
+    }
+    Ok(results)
+}
+
+/// Parse a CoNLL-U document, see [`read`].
+pub fn parse(text: &str) -> Result<Vec<LTPResult>> {
+    read(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::conllu;
+    use crate::{Entity, LTPResult, DEP, SDP};
+
+    #[test]
+    fn test_conllu() {
+        let dep = |arc: usize, rel: &str| DEP {
+            arc,
+            rel: rel.to_string(),
+        };
+        let sdp = |src: usize, tgt: usize, rel: &str| SDP {
+            src,
+            tgt,
+            rel: rel.to_string(),
+        };
+        let result = LTPResult {
+            seg: Some(vec!["他".into(), "叫".into(), "汤姆".into()]),
+            char_offsets: Some(vec![(0, 1), (1, 2), (2, 4)]),
+            byte_offsets: Some(vec![(0, 3), (3, 6), (6, 12)]),
+            pos: Some(vec!["r".into(), "v".into(), "nh".into()]),
+            ner: Some(vec!["O".into(), "O".into(), "S-Nh".into()]),
+            entities: Some(vec![Entity {
+                label: "Nh".into(),
+                start_word: 2,
+                end_word: 2,
+                text: "汤姆".into(),
+                char_start: 2,
+                char_end: 4,
+            }]),
+            dep: Some(vec![dep(2, "SBV"), dep(0, "HED"), dep(2, "DBL")]),
+            sdp: Some(vec![
+                sdp(1, 2, "AGT"),
+                sdp(2, 0, "Root"),
+                sdp(3, 2, "DATV"),
+                sdp(3, 2, "eSUCC"),
+            ]),
+            ..LTPResult::default()
+        };
+
+        let text = conllu::to_string(std::slice::from_ref(&result));
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "# text = 他叫汤姆");
+        assert_eq!(
+            lines[3],
+            "3\t汤姆\t_\tPROPN\tnh\t_\t2\tDBL\t2:DATV|2:eSUCC\tNER=S-Nh|TokenRange=2:4"
+        );
+        let parsed = conllu::parse(&text).unwrap();
+        assert_eq!(
+            serde_json::to_string(&parsed).unwrap(),
+            serde_json::to_string(&[result]).unwrap()
+        );
+
+        // a multiword token and the words of a sentence with spaces
+        let text = "# sent_id = 1\n1-2\tdu\t_\t_\t_\t_\t_\t_\t_\t_\n\
+            1\tde\t_\tADP\t_\t_\t2\tcase\t_\t_\n\
+            2\tle\t_\tDET\t_\t_\t0\troot\t_\tSpaceAfter=No\n\
+            3\t.\t_\tPUNCT\t_\t_\t2\tpunct\t_\t_\n";
+        let parsed = conllu::parse(text).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].pos.as_ref().unwrap()[2], "PUNCT");
+        assert_eq!(parsed[0].char_offsets.as_ref().unwrap()[2], (5, 6));
+        assert!(parsed[0].sdp.is_none() && parsed[0].ner.is_none());
+        assert!(conllu::parse("1\tde\n").is_err());
+        let text = "1\tde\t_\t_\t_\t_\t_\t_\t_\tTokenRange=0:5\n";
+        assert_eq!(
+            conllu::parse(text).unwrap()[0].char_offsets,
+            Some(vec![(0, 2)])
+        );
+        assert!(conllu::parse("1\tde\t_\t_\t_\t_\t_\t_\t_\tTokenRange=5:2\n").is_err());
+        let text = "1\tde\t_\t_\t_\t_\t0\troot\t0:root|1.1:nsubj\t_\n";
+        assert_eq!(
+            conllu::parse(text).unwrap()[0].sdp.as_ref().unwrap().len(),
+            1
+        );
+
+        // edges and tags past the words of a hand built result are skipped
+        let result = LTPResult {
+            seg: Some(vec!["他".into(), "叫".into()]),
+            pos: Some(vec!["r".into()]),
+            sdp: Some(vec![
+                sdp(1, 2, "AGT"),
+                sdp(5, 0, "Root"),
+                sdp(2, 9, "eSUCC"),
+            ]),
+            ..LTPResult::default()
+        };
+        let text = conllu::to_string(&[result]);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "1\t他\t_\tPRON\tr\t_\t_\t_\t2:AGT\tSpaceAfter=No");
+        assert_eq!(lines[2], "2\t叫\t_\t_\t_\t_\t_\t_\t_\t_");
+    }
+}
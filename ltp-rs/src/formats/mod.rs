@@ -0,0 +1,53 @@
+//! Conversion of [`LTPResult`](crate::LTPResult)s from and to the interchange formats of other
+//! tools.
+
+pub mod conll09;
+pub mod conllu;
+pub mod semeval;
+pub mod xml;
+
+use crate::LTPResult;
+
+/// Text of a sentence from its words, the gaps between the char offsets of the words, if any,
+/// are filled with spaces.
+pub(crate) fn sentence_text(result: &LTPResult) -> Option<String> {
+    let seg = result.seg.as_ref()?;
+    match result.char_offsets.as_ref() {
+        Some(offsets) => {
+            let mut text = String::new();
+            let mut length = 0;
+            for (word, &(start, end)) in seg.iter().zip(offsets) {
+                text.push_str(&" ".repeat(start.saturating_sub(length)));
+                text.push_str(word);
+                length = end.max(length);
+            }
+            Some(text)
+        }
+        None => Some(seg.concat()),
+    }
+}
+
+/// Semantic heads with their relations of the words counted from 1, the root being `0`, edges
+/// from or to words past `word_num` are skipped.
+pub(crate) fn semantic_heads(result: &LTPResult, word_num: usize) -> Vec<Vec<(usize, &str)>> {
+    let mut heads = vec![Vec::new(); word_num + 1];
+    for edge in result.sdp.iter().flatten() {
+        if edge.src > 0 && edge.src <= word_num && edge.tgt <= word_num {
+            heads[edge.src].push((edge.tgt, edge.rel.as_str()));
+        }
+    }
+    heads
+}
+
+/// Column of the `idx`th word of a per word field, `_` when it is missing
+pub(crate) fn word_column(field: Option<&Vec<String>>, idx: usize) -> &str {
+    field.and_then(|x| x.get(idx)).map_or("_", |x| x.as_str())
+}
+
+/// `None` for the `_` of empty columns
+pub(crate) fn column(value: &str) -> Option<&str> {
+    match value {
+        "_" | "" => None,
+        value => Some(value),
+    }
+}
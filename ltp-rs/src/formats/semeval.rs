@@ -0,0 +1,192 @@
+//! The format of the [SemEval-2016 Chinese Semantic Dependency Parsing](https://github.com/HIT-SCIR/SemEval-2016)
+//! task, CoNLL-X columns `ID FORM LEMMA CPOSTAG POSTAG FEATS HEAD DEPREL PHEAD PDEPREL` with one
+//! line per edge of the semantic graph, a word with several heads is repeated on consecutive
+//! lines, and the virtual root is head `0`.
+
+use std::io::{BufRead, Write};
+
+use itertools::Itertools;
+
+use crate::formats::{column, semantic_heads, word_column};
+use crate::{LTPError, LTPResult, Result, SDP};
+
+/// Write the semantic graphs of `results` in the SemEval-2016 format, one sentence each, a word
+/// without head is written once with `_` as HEAD and DEPREL.
+pub fn write<W: Write>(writer: &mut W, results: &[LTPResult]) -> std::io::Result<()> {
+    for result in results {
+        let word_num = result.word_num();
+        let heads = semantic_heads(result, word_num);
+
+        for idx in 0..word_num {
+            let form = word_column(result.seg.as_ref(), idx);
+            let pos = word_column(result.pos.as_ref(), idx);
+            let mut line = |head: &str, rel: &str| {
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}\t{}\t_\t{}\t{}\t_\t_",
+                    idx + 1,
+                    form,
+                    form,
+                    pos,
+                    pos,
+                    head,
+                    rel
+                )
+            };
+            if heads[idx + 1].is_empty() {
+                line("_", "_")?;
+            }
+            for (head, rel) in heads[idx + 1].iter().sorted() {
+                line(&head.to_string(), rel)?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// `results` as a SemEval-2016 document.
+pub fn to_string(results: &[LTPResult]) -> String {
+    let mut buffer = Vec::new();
+    write(&mut buffer, results).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+struct Word<'a> {
+    form: &'a str,
+    pos: Option<&'a str>,
+    heads: Vec<(usize, &'a str)>,
+}
+
+fn invalid(line_no: usize, message: &str) -> LTPError {
+    LTPError::InvalidInput(format!("SemEval-2016 line {}: {}", line_no + 1, message))
+}
+
+/// add the edge of a line to the last word when it repeats it, or as a new word
+fn parse_line<'a>(words: &mut Vec<Word<'a>>, line: &'a str, line_no: usize) -> Result<()> {
+    let columns = line.split('\t').collect_vec();
+    if columns.len() < 8 {
+        return Err(invalid(
+            line_no,
+            "expected at least 8 tab separated columns",
+        ));
+    }
+    let number = |x: &str| {
+        x.parse::<usize>()
+            .map_err(|_| invalid(line_no, "bad index"))
+    };
+
+    let id = number(columns[0])?;
+    if id == 0 || (id != words.len() && id != words.len() + 1) {
+        return Err(invalid(line_no, "words are not numbered in order"));
+    }
+    if id == words.len() + 1 {
+        words.push(Word {
+            form: columns[1],
+            pos: column(columns[4]).or_else(|| column(columns[3])),
+            heads: Vec::new(),
+        });
+    }
+    if let (Some(head), Some(rel)) = (column(columns[6]), column(columns[7])) {
+        words.last_mut().unwrap().heads.push((number(head)?, rel));
+    }
+    Ok(())
+}
+
+fn to_result(words: &[Word], line_no: usize) -> Result<LTPResult> {
+    let mut result = LTPResult {
+        seg: Some(words.iter().map(|x| x.form.to_string()).collect()),
+        ..LTPResult::default()
+    };
+    if words.iter().all(|x| x.pos.is_some()) {
+        result.pos = Some(words.iter().map(|x| x.pos.unwrap().to_string()).collect());
+    }
+    if words
+        .iter()
+        .flat_map(|x| &x.heads)
+        .any(|&(head, _)| head > words.len())
+    {
+        return Err(invalid(line_no, "HEAD out of the sentence"));
+    }
+    let sdp = words.iter().enumerate().flat_map(|(idx, x)| {
+        x.heads.iter().map(move |&(head, rel)| SDP {
+            src: idx + 1,
+            tgt: head,
+            rel: rel.to_string(),
+        })
+    });
+    result.sdp = Some(sdp.collect());
+    Ok(result)
+}
+
+/// Read the sentences of a SemEval-2016 document, the edges of every word are gathered from its
+/// consecutive lines.
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<LTPResult>> {
+    let lines = reader.lines().collect::<std::io::Result<Vec<_>>>()?;
+    let mut results = Vec::new();
+    let mut words = Vec::new();
+    for (line_no, line) in lines.iter().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            if !words.is_empty() {
+                results.push(to_result(&words, line_no)?);
+                words.clear();
+            }
+        } else if !line.starts_with('#') {
+            parse_line(&mut words, line, line_no)?;
+        }
+    }
+    if !words.is_empty() {
+        results.push(to_result(&words, lines.len())?);
+    }
+    Ok(results)
+}
+
+/// Parse a SemEval-2016 document, see [`read`].
+pub fn parse(text: &str) -> Result<Vec<LTPResult>> {
+    read(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::semeval;
+    use crate::{LTPResult, SDP};
+
+    #[test]
+    fn test_semeval() {
+        let sdp = |src: usize, tgt: usize, rel: &str| SDP {
+            src,
+            tgt,
+            rel: rel.to_string(),
+        };
+        let result = LTPResult {
+            seg: Some(vec!["他".into(), "叫".into(), "汤姆".into(), "去".into()]),
+            pos: Some(vec!["r".into(), "v".into(), "nh".into(), "v".into()]),
+            sdp: Some(vec![
+                sdp(1, 2, "AGT"),
+                sdp(2, 0, "Root"),
+                sdp(3, 2, "DATV"),
+                sdp(3, 4, "AGT"),
+                sdp(4, 2, "eSUCC"),
+            ]),
+            ..LTPResult::default()
+        };
+
+        let text = semeval::to_string(std::slice::from_ref(&result));
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "2\t叫\t叫\tv\tv\t_\t0\tRoot\t_\t_");
+        assert_eq!(lines[2], "3\t汤姆\t汤姆\tnh\tnh\t_\t2\tDATV\t_\t_");
+        assert_eq!(lines[3], "3\t汤姆\t汤姆\tnh\tnh\t_\t4\tAGT\t_\t_");
+        let parsed = semeval::parse(&text).unwrap();
+        assert_eq!(
+            serde_json::to_string(&parsed).unwrap(),
+            serde_json::to_string(&[result]).unwrap()
+        );
+
+        let text = "1\t他\t他\tr\tr\t_\t0\tRoot\t_\t_\n3\t去\t去\tv\tv\t_\t1\tAGT\t_\t_\n";
+        assert!(semeval::parse(text).is_err());
+        assert!(semeval::parse("0\t他\t他\tr\tr\t_\t0\tRoot\t_\t_\n").is_err());
+        let text = "1\t他\t他\tr\tr\t_\t2\tRoot\t_\t_\n";
+        assert!(semeval::parse(text).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! The `xml4nlp` document of the older LTP releases (`ltp_server`, `ltp_test`), paragraphs of
+//! sentences of words:
+//!
+//! ```xml
+//! <xml4nlp>
+//!     <note sent="y" word="y" pos="y" ne="y" parser="y" wsd="n" srl="y" semparser="y" />
+//!     <doc>
+//!         <para id="0">
+//!             <sent id="0" cont="他叫汤姆">
+//!                 <word id="0" cont="他" pos="r" ne="O" parent="1" relate="SBV">
+//!                     <sem id="0" parent="1" relate="AGT" />
+//!                 </word>
+//!                 ...
+//! ```
+//!
+//! Words are counted from `0` in every sentence and the root is parent `-1`, the arguments of
+//! a predicate are `<arg id type beg end />` and its semantic heads `<sem id parent relate />`
+//! children of its word.
+
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::formats::{semantic_heads, sentence_text};
+use crate::LTPResult;
+
+/// `value` escaped for an attribute
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// head counted from `1` as a parent counted from `0`, the root being `-1`
+fn parent(head: usize) -> isize {
+    head as isize - 1
+}
+
+/// Write `paragraphs` of results, one per sentence, as an `xml4nlp` document, the `<note>`
+/// flags the tasks filled in any of them.
+pub fn write<W: Write, P: AsRef<[LTPResult]>>(
+    writer: &mut W,
+    paragraphs: &[P],
+) -> std::io::Result<()> {
+    let results = || paragraphs.iter().flat_map(|x| x.as_ref());
+    let flag = |filled: fn(&LTPResult) -> bool| match results().any(filled) {
+        true => "y",
+        false => "n",
+    };
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="utf-8" ?>"#)?;
+    writeln!(writer, "<xml4nlp>")?;
+    writeln!(
+        writer,
+        r#"    <note sent="y" word="{}" pos="{}" ne="{}" parser="{}" wsd="n" srl="{}" semparser="{}" />"#,
+        flag(|x| x.seg.is_some()),
+        flag(|x| x.pos.is_some()),
+        flag(|x| x.ner.is_some()),
+        flag(|x| x.dep.is_some()),
+        flag(|x| x.srl.is_some()),
+        flag(|x| x.sdp.is_some()),
+    )?;
+    writeln!(writer, "    <doc>")?;
+    for (para_id, paragraph) in paragraphs.iter().enumerate() {
+        writeln!(writer, r#"        <para id="{}">"#, para_id)?;
+        for (sent_id, result) in paragraph.as_ref().iter().enumerate() {
+            let text = sentence_text(result).unwrap_or_default();
+            writeln!(
+                writer,
+                r#"            <sent id="{}" cont="{}">"#,
+                sent_id,
+                escape(&text)
+            )?;
+            let word_num = result.word_num();
+            let heads = semantic_heads(result, word_num);
+
+            for idx in 0..word_num {
+                let mut attributes = vec![format!(r#"id="{}""#, idx)];
+                if let Some(seg) = result.seg.as_ref().and_then(|x| x.get(idx)) {
+                    attributes.push(format!(r#"cont="{}""#, escape(seg)));
+                }
+                if let Some(pos) = result.pos.as_ref().and_then(|x| x.get(idx)) {
+                    attributes.push(format!(r#"pos="{}""#, escape(pos)));
+                }
+                if let Some(ner) = result.ner.as_ref().and_then(|x| x.get(idx)) {
+                    attributes.push(format!(r#"ne="{}""#, escape(ner)));
+                }
+                if let Some(dep) = result.dep.as_ref().and_then(|x| x.get(idx)) {
+                    attributes.push(format!(r#"parent="{}""#, parent(dep.arc)));
+                    attributes.push(format!(r#"relate="{}""#, escape(&dep.rel)));
+                }
+
+                let mut children = Vec::new();
+                let frame = result.srl.iter().flatten().find(|x| x.predicate == idx);
+                for (arg_id, arg) in frame.iter().flat_map(|x| &x.arguments).enumerate() {
+                    children.push(format!(
+                        r#"<arg id="{}" type="{}" beg="{}" end="{}" />"#,
+                        arg_id,
+                        escape(&arg.role),
+                        arg.start,
+                        arg.end
+                    ));
+                }
+                for (sem_id, (head, rel)) in heads[idx + 1].iter().sorted().enumerate() {
+                    children.push(format!(
+                        r#"<sem id="{}" parent="{}" relate="{}" />"#,
+                        sem_id,
+                        parent(*head),
+                        escape(rel)
+                    ));
+                }
+
+                let word = format!("                <word {}", attributes.join(" "));
+                match children.is_empty() {
+                    true => writeln!(writer, "{} />", word)?,
+                    false => {
+                        writeln!(writer, "{}>", word)?;
+                        for child in children {
+                            writeln!(writer, "                    {}", child)?;
+                        }
+                        writeln!(writer, "                </word>")?;
+                    }
+                }
+            }
+            writeln!(writer, "            </sent>")?;
+        }
+        writeln!(writer, "        </para>")?;
+    }
+    writeln!(writer, "    </doc>")?;
+    writeln!(writer, "</xml4nlp>")?;
+    Ok(())
+}
+
+/// `paragraphs` as an `xml4nlp` document.
+pub fn to_string<P: AsRef<[LTPResult]>>(paragraphs: &[P]) -> String {
+    let mut buffer = Vec::new();
+    write(&mut buffer, paragraphs).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::xml;
+    use crate::{LTPResult, SrlArg, SrlFrame, DEP, SDP};
+
+    #[test]
+    fn test_xml() {
+        let dep = |arc: usize, rel: &str| DEP {
+            arc,
+            rel: rel.to_string(),
+        };
+        let sdp = |src: usize, tgt: usize, rel: &str| SDP {
+            src,
+            tgt,
+            rel: rel.to_string(),
+        };
+        let result = LTPResult {
+            seg: Some(vec!["他".into(), "叫".into(), "<汤姆>".into()]),
+            pos: Some(vec!["r".into(), "v".into(), "nh".into()]),
+            ner: Some(vec!["O".into(), "O".into(), "S-Nh".into()]),
+            dep: Some(vec![dep(2, "SBV"), dep(0, "HED"), dep(2, "DBL")]),
+            srl: Some(vec![SrlFrame {
+                predicate: 1,
+                arguments: vec![SrlArg {
+                    role: "A0".into(),
+                    start: 0,
+                    end: 0,
+                }],
+            }]),
+            sdp: Some(vec![
+                sdp(1, 2, "AGT"),
+                sdp(2, 0, "Root"),
+                sdp(3, 2, "eSUCC"),
+                sdp(3, 2, "DATV"),
+            ]),
+            ..LTPResult::default()
+        };
+        let text = xml::to_string(&[vec![result], vec![LTPResult::default()]]);
+        let lines = text.lines().map(|x| x.trim()).collect::<Vec<_>>();
+        assert_eq!(
+            lines[2],
+            r#"<note sent="y" word="y" pos="y" ne="y" parser="y" wsd="n" srl="y" semparser="y" />"#
+        );
+        assert_eq!(lines[5], r#"<sent id="0" cont="他叫&lt;汤姆&gt;">"#);
+        assert_eq!(
+            &lines[9..13],
+            &[
+                r#"<word id="1" cont="叫" pos="v" ne="O" parent="-1" relate="HED">"#,
+                r#"<arg id="0" type="A0" beg="0" end="0" />"#,
+                r#"<sem id="0" parent="-1" relate="Root" />"#,
+                "</word>",
+            ]
+        );
+        assert_eq!(
+            lines[13],
+            r#"<word id="2" cont="&lt;汤姆&gt;" pos="nh" ne="S-Nh" parent="1" relate="DBL">"#
+        );
+        assert_eq!(lines[14], r#"<sem id="0" parent="1" relate="DATV" />"#);
+        assert_eq!(
+            &lines[19..22],
+            &[r#"<para id="1">"#, r#"<sent id="0" cont="">"#, "</sent>"]
+        );
+    }
+}
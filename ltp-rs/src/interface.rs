@@ -1,27 +1,78 @@
 use std::ffi::OsStr;
 use std::path::Path;
 
-use itertools::{multizip, Itertools};
+use bitflags::bitflags;
+use itertools::Itertools;
 use lazy_static::lazy_static;
-use std::borrow::Borrow;
 use tokenizers::tokenizer::EncodeInput;
 
 use onnxruntime as onnx;
 use onnxruntime::environment::Environment;
-use onnxruntime::ndarray::{prelude::s, ArrayBase};
+use onnxruntime::ndarray::{prelude::s, ArrayBase, ArrayD, ArrayViewD, IxDyn};
 use onnxruntime::session::Session;
 use onnxruntime::tensor::{DynOrtTensor, OrtOwnedTensor};
-use onnxruntime::GraphOptimizationLevel;
+use onnxruntime::{GraphOptimizationLevel, TensorElementDataType};
 use serde::{Deserialize, Serialize};
 
+use crate::align::{Alignment, ArcPairs};
+use crate::batch::{self, BatchPolicy};
+use crate::dictionary::Dictionary;
+use crate::pool::SessionPool;
+use crate::scores;
 use crate::tokenizer::{LTPTokenizer, Tokenizer};
 use crate::vocabs::Vocab;
+use crate::window;
 
-use crate::eisner::eisner;
-use crate::entities::get_entities;
-use crate::viterbi::viterbi_decode_postprocess;
+use crate::eisner::{eisner_with_root, RootPolicy};
+use crate::entities::{allowed_transitions, get_entities};
+use crate::mst::mst_with_root;
+use crate::sdp::{self, SdpPolicy};
+use crate::sentence::split_sentences;
+use crate::stream::AnalyzeIter;
+use crate::viterbi::{
+    crf_forward, viterbi_decode, viterbi_decode_kbest, viterbi_decode_postprocess,
+};
 
-use crate::Result;
+use crate::{LTPError, Result};
+
+bitflags! {
+    /// Tasks decoded by [`LTP::pipeline_batch_with`], combine them with `|`.
+    pub struct Tasks: u32 {
+        const SEG = 0b000001;
+        const POS = 0b000010;
+        const NER = 0b000100;
+        const SRL = 0b001000;
+        const DEP = 0b010000;
+        const SDP = 0b100000;
+    }
+}
+
+/// How dependency trees, and the backbones of semantic graphs, are decoded from arc scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreeDecoder {
+    /// Eisner, arcs never cross
+    #[default]
+    Projective,
+    /// Chu-Liu/Edmonds, arcs may cross
+    NonProjective,
+}
+
+impl TreeDecoder {
+    /// Heads of every word, `scores` and `stn_length` are laid out as for
+    /// [`crate::eisner::eisner`].
+    pub fn decode(
+        &self,
+        scores: &[f32],
+        stn_length: &[usize],
+        remove_root: bool,
+        root: RootPolicy,
+    ) -> Vec<Vec<usize>> {
+        match self {
+            TreeDecoder::Projective => eisner_with_root(scores, stn_length, remove_root, root),
+            TreeDecoder::NonProjective => mst_with_root(scores, stn_length, remove_root, root),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DEP {
@@ -36,14 +87,358 @@ pub struct SDP {
     pub rel: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// A named entity over the words `start_word..=end_word`, `[char_start, char_end)` are its char
+/// offsets in the input sentence.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Entity {
+    pub label: String,
+    pub start_word: usize,
+    pub end_word: usize,
+    pub text: String,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+impl Entity {
+    /// entities of the BIOES `tags` of a sentence, `spans` are the char offsets of its words
+    /// in `chars`
+    pub(crate) fn from_tags<S: AsRef<str>>(
+        tags: &[S],
+        spans: &[(usize, usize)],
+        chars: &[char],
+    ) -> Vec<Entity> {
+        get_entities(tags.iter().map(|x| x.as_ref()).collect())
+            .into_iter()
+            .map(|(label, start, end)| {
+                let (char_start, char_end) = (spans[start].0, spans[end].1);
+                Entity {
+                    label: label.to_string(),
+                    start_word: start,
+                    end_word: end,
+                    text: chars[char_start..char_end].iter().collect(),
+                    char_start,
+                    char_end,
+                }
+            })
+            .collect_vec()
+    }
+}
+
+/// An argument of an [`SrlFrame`], spanning the words `start..=end`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SrlArg {
+    pub role: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A predicate with its arguments, word indices start from `0` as in `seg`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SrlFrame {
+    pub predicate: usize,
+    pub arguments: Vec<SrlArg>,
+}
+
+impl SrlFrame {
+    /// frames of the words whose tag rows hold arguments, `rows` holds the BIO tags of every
+    /// word of the sentence as predicate
+    fn from_rows<R: AsRef<[S]>, S: AsRef<str>>(rows: &[R]) -> Vec<SrlFrame> {
+        rows.iter()
+            .enumerate()
+            .filter_map(|(predicate, tags)| {
+                let tags = tags.as_ref().iter().map(|x| x.as_ref()).collect();
+                let arguments = get_entities(tags)
+                    .into_iter()
+                    .map(|(role, start, end)| SrlArg {
+                        role: role.to_string(),
+                        start,
+                        end,
+                    })
+                    .collect_vec();
+                match arguments.is_empty() {
+                    true => None,
+                    false => Some(SrlFrame {
+                        predicate,
+                        arguments,
+                    }),
+                }
+            })
+            .collect_vec()
+    }
+}
+
+/// Confidence of the decoded outputs in `[0, 1]`, every field is aligned with the field of
+/// the same name in [`LTPResult`].
+///
+/// Segmentation, POS, NER and SRL confidences are only available for models exporting the
+/// scores of these heads rather than the decoded labels, dependency arcs and SDP edges are
+/// always scored.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Confidence {
+    /// product of the probabilities of the tags of the word's chars, pre-segmented inputs and
+    /// segmentations constrained by a user dictionary have none
+    pub seg: Option<Vec<f32>>,
+    pub pos: Option<Vec<f32>>,
+    pub ner: Option<Vec<f32>>,
+    /// probability of the tag row of every frame's predicate, only for models exporting SRL
+    /// emissions and transitions
+    pub srl: Option<Vec<f32>>,
+    /// softmax of the arc over the candidate heads of the word, multiplied by the probability
+    /// of the relation when the model exports relation logits
+    pub dep: Option<Vec<f32>>,
+    /// sigmoid of the edge score, multiplied by the probability of the relation when the
+    /// model exports relation logits
+    pub sdp: Option<Vec<f32>>,
+}
+
+impl Confidence {
+    fn extend(&mut self, other: Confidence) {
+        fn extend(this: &mut Option<Vec<f32>>, other: Option<Vec<f32>>) {
+            if let (Some(this), Some(other)) = (this.as_mut(), other) {
+                this.extend(other);
+            }
+        }
+        extend(&mut self.seg, other.seg);
+        extend(&mut self.pos, other.pos);
+        extend(&mut self.ner, other.ner);
+        extend(&mut self.srl, other.srl);
+        extend(&mut self.dep, other.dep);
+        extend(&mut self.sdp, other.sdp);
+    }
+}
+
+/// A decoded sequence of words or tags with its score.
+pub type Scored = (Vec<String>, f32);
+
+/// Alternative outputs best first, the first one is the output of [`LTPResult`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct KBest {
+    /// segmentations with their log probabilities, only for models exporting segmentation
+    /// logits, pre-segmented inputs, segmentations constrained by a user dictionary and inputs
+    /// split into windows have none
+    pub seg: Option<Vec<Scored>>,
+    /// tag rows of every word as predicate with their path scores, the best rows make up the
+    /// frames of [`LTPResult::srl`], only for models exporting SRL emissions and transitions,
+    /// given words the model does not take as predicate have no rows
+    pub srl: Option<Vec<Vec<Scored>>>,
+    /// relations of every dependency arc with their probabilities, only for models exporting
+    /// relation logits
+    pub dep: Option<Vec<Vec<(String, f32)>>>,
+    /// relations of every semantic edge with their probabilities, only for models exporting
+    /// relation logits
+    pub sdp: Option<Vec<Vec<(String, f32)>>>,
+}
+
+impl KBest {
+    fn extend(&mut self, other: KBest, word_bias: usize, other_word_num: usize) {
+        // alternatives of the windows do not combine into alternatives of the whole input
+        self.seg = None;
+        if let (Some(srl), Some(other)) = (self.srl.as_mut(), other.srl) {
+            for paths in srl.iter_mut() {
+                for (tags, _score) in paths.iter_mut() {
+                    tags.extend(vec![String::from("O"); other_word_num]);
+                }
+            }
+            for mut paths in other {
+                for (tags, _score) in paths.iter_mut() {
+                    let mut shifted = vec![String::from("O"); word_bias];
+                    shifted.append(tags);
+                    *tags = shifted;
+                }
+                srl.push(paths);
+            }
+        }
+        if let (Some(dep), Some(other)) = (self.dep.as_mut(), other.dep) {
+            dep.extend(other);
+        }
+        if let (Some(sdp), Some(other)) = (self.sdp.as_mut(), other.sdp) {
+            sdp.extend(other);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct LTPResult {
     pub seg: Option<Vec<String>>,
+    /// `[start, end)` char offsets of the words in the input sentence
+    pub char_offsets: Option<Vec<(usize, usize)>>,
+    /// `[start, end)` UTF-8 byte offsets of the words in the input sentence
+    pub byte_offsets: Option<Vec<(usize, usize)>>,
     pub pos: Option<Vec<String>>,
     pub ner: Option<Vec<String>>,
+    /// entities decoded from the `ner` tags
+    pub entities: Option<Vec<Entity>>,
     pub dep: Option<Vec<DEP>>,
     pub sdp: Option<Vec<SDP>>,
-    pub srl: Option<Vec<Vec<String>>>,
+    /// frames of the words taken as predicates, only words with arguments make frames
+    pub srl: Option<Vec<SrlFrame>>,
+    /// only filled when enabled with [`LTP::set_confidence`]
+    pub confidence: Option<Confidence>,
+    /// only filled when enabled with [`LTP::set_kbest`]
+    pub kbest: Option<KBest>,
+}
+
+impl LTPResult {
+    pub(crate) fn word_num(&self) -> usize {
+        self.seg
+            .as_ref()
+            .map(|x| x.len())
+            .or_else(|| self.pos.as_ref().map(|x| x.len()))
+            .or_else(|| self.ner.as_ref().map(|x| x.len()))
+            .or_else(|| self.dep.as_ref().map(|x| x.len()))
+            .or_else(|| {
+                // frames only reach the words up to the last predicate or argument
+                let srl = self.srl.as_ref()?;
+                let last = srl
+                    .iter()
+                    .flat_map(|x| {
+                        std::iter::once(x.predicate).chain(x.arguments.iter().map(|x| x.end))
+                    })
+                    .max();
+                Some(last.map_or(0, |x| x + 1))
+            })
+            .or_else(|| {
+                let sdp = self.sdp.as_ref()?;
+                Some(sdp.iter().map(|x| x.src).max().unwrap_or(0))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Append the result of the next window of a long input, word indices of `other` are
+    /// shifted after the `word_bias` words of `self`, while arcs to the root stay on the root.
+    ///
+    /// The word counts come from the decoding, results of some tasks alone do not tell them.
+    fn extend(
+        &mut self,
+        other: LTPResult,
+        word_bias: usize,
+        other_word_num: usize,
+        char_bias: usize,
+        byte_bias: usize,
+    ) {
+        if let (Some(seg), Some(other)) = (self.seg.as_mut(), other.seg) {
+            seg.extend(other);
+        }
+        if let (Some(offsets), Some(other)) = (self.char_offsets.as_mut(), other.char_offsets) {
+            offsets.extend(
+                other
+                    .into_iter()
+                    .map(|(start, end)| (start + char_bias, end + char_bias)),
+            );
+        }
+        if let (Some(offsets), Some(other)) = (self.byte_offsets.as_mut(), other.byte_offsets) {
+            offsets.extend(
+                other
+                    .into_iter()
+                    .map(|(start, end)| (start + byte_bias, end + byte_bias)),
+            );
+        }
+        if let (Some(pos), Some(other)) = (self.pos.as_mut(), other.pos) {
+            pos.extend(other);
+        }
+        if let (Some(ner), Some(other)) = (self.ner.as_mut(), other.ner) {
+            ner.extend(other);
+        }
+        if let (Some(entities), Some(other)) = (self.entities.as_mut(), other.entities) {
+            entities.extend(other.into_iter().map(|x| Entity {
+                start_word: x.start_word + word_bias,
+                end_word: x.end_word + word_bias,
+                char_start: x.char_start + char_bias,
+                char_end: x.char_end + char_bias,
+                ..x
+            }));
+        }
+        if let (Some(srl), Some(other)) = (self.srl.as_mut(), other.srl) {
+            srl.extend(other.into_iter().map(|frame| {
+                SrlFrame {
+                    predicate: frame.predicate + word_bias,
+                    arguments: frame
+                        .arguments
+                        .into_iter()
+                        .map(|x| SrlArg {
+                            role: x.role,
+                            start: x.start + word_bias,
+                            end: x.end + word_bias,
+                        })
+                        .collect_vec(),
+                }
+            }));
+        }
+        let shift = |idx: usize| if idx == 0 { 0 } else { idx + word_bias };
+        if let (Some(dep), Some(other)) = (self.dep.as_mut(), other.dep) {
+            dep.extend(other.into_iter().map(|x| DEP {
+                arc: shift(x.arc),
+                rel: x.rel,
+            }));
+        }
+        if let (Some(sdp), Some(other)) = (self.sdp.as_mut(), other.sdp) {
+            sdp.extend(other.into_iter().map(|x| SDP {
+                src: shift(x.src),
+                tgt: shift(x.tgt),
+                rel: x.rel,
+            }));
+        }
+        if let (Some(confidence), Some(other)) = (self.confidence.as_mut(), other.confidence) {
+            confidence.extend(other);
+        }
+        if let (Some(kbest), Some(other)) = (self.kbest.as_mut(), other.kbest) {
+            kbest.extend(other, word_bias, other_word_num);
+        }
+    }
+
+    /// `[start, end)` char offsets covering the words `start..=end`, as spanned by NER
+    /// entities and SRL arguments.
+    pub fn char_span(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        let offsets = self.char_offsets.as_ref()?;
+        Some((offsets.get(start)?.0, offsets.get(end)?.1))
+    }
+
+    /// `[start, end)` UTF-8 byte offsets covering the words `start..=end`, as spanned by NER
+    /// entities and SRL arguments.
+    pub fn byte_span(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        let offsets = self.byte_offsets.as_ref()?;
+        Some((offsets.get(start)?.0, offsets.get(end)?.1))
+    }
+}
+
+/// An input or an output of the ONNX model, `None` dimensions are dynamic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TensorInfo {
+    pub name: String,
+    /// element type as named by ONNX Runtime, such as `Int64` or `Float`
+    pub element_type: String,
+    pub dimensions: Vec<Option<u32>>,
+}
+
+/// Inputs and outputs of the ONNX model, in the order they are fed and read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Signature {
+    pub inputs: Vec<TensorInfo>,
+    pub outputs: Vec<TensorInfo>,
+}
+
+impl Signature {
+    fn of(session: &Session) -> Signature {
+        let info = |name: &str, element_type: TensorElementDataType, dimensions: &[Option<u32>]| {
+            TensorInfo {
+                name: name.to_string(),
+                element_type: format!("{:?}", element_type),
+                dimensions: dimensions.to_vec(),
+            }
+        };
+        Signature {
+            inputs: session
+                .inputs
+                .iter()
+                .map(|x| info(&x.name, x.input_type, &x.dimensions))
+                .collect(),
+            outputs: session
+                .outputs
+                .iter()
+                .map(|x| info(&x.name, x.output_type, &x.dimensions))
+                .collect(),
+        }
+    }
 }
 
 lazy_static! {
@@ -54,115 +449,1125 @@ lazy_static! {
             .build().unwrap();
 }
 
+/// Heads exporting float logits instead of label ids, their labels are decoded by argmax and
+/// their probabilities are available as confidence.
+///
+/// A head exports logits when its output is named `seg_logits`, `pos_logits`, `ner_logits`,
+/// `dep_label_logits` or `sdp_label_logits`. SRL exports `[batch, predicate, word, label]`
+/// emissions named `srl_emissions` and `[label, label]` transitions named `srl_transitions` in
+/// place of the Viterbi history and last tags, with the start and end scores optionally
+/// stacked below the transitions as two more rows, and is decoded by the CRF on our side.
+#[derive(Debug, Clone, Copy, Default)]
+struct Logits {
+    seg: bool,
+    pos: bool,
+    ner: bool,
+    srl: bool,
+    dep_labels: bool,
+    sdp_labels: bool,
+}
+
+impl Logits {
+    /// Find the logits among the outputs of the model, they must be float tensors of the
+    /// expected rank read in place of the ids of their head.
+    fn of(signature: &Signature, vocabs: &Vocab) -> Result<Logits> {
+        // outputs are read in order, one for every tagging head and two for the others, with
+        // the name and rank they have when they hold logits
+        let heads = [
+            (vocabs.seg.is_some(), vec![Some(("seg_logits", 3))]),
+            (vocabs.pos.is_some(), vec![Some(("pos_logits", 3))]),
+            (vocabs.ner.is_some(), vec![Some(("ner_logits", 3))]),
+            (
+                vocabs.srl.is_some(),
+                vec![Some(("srl_emissions", 4)), Some(("srl_transitions", 2))],
+            ),
+            (
+                vocabs.dep.is_some(),
+                vec![None, Some(("dep_label_logits", 4))],
+            ),
+            (
+                vocabs.sdp.is_some(),
+                vec![None, Some(("sdp_label_logits", 4))],
+            ),
+        ];
+        let slots = heads
+            .iter()
+            .filter(|(present, _slots)| *present)
+            .flat_map(|(_present, slots)| slots.iter().cloned())
+            .collect_vec();
+        if signature.outputs.len() < slots.len() {
+            return Err(LTPError::InvalidInput(format!(
+                "the model has {} outputs but its heads read {}",
+                signature.outputs.len(),
+                slots.len()
+            )));
+        }
+
+        let find = |name: &str| -> Result<bool> {
+            let idx = match signature.outputs.iter().position(|x| x.name == name) {
+                Some(idx) => idx,
+                None => return Ok(false),
+            };
+            let output = &signature.outputs[idx];
+            match slots.get(idx) {
+                Some(&Some((slot, rank)))
+                    if slot == name
+                        && output.element_type == "Float"
+                        && output.dimensions.len() == rank =>
+                {
+                    Ok(true)
+                }
+                _ => Err(LTPError::InvalidInput(format!(
+                    "output `{}` is not a float tensor read in place of its head",
+                    name
+                ))),
+            }
+        };
+        let srl = find("srl_emissions")?;
+        if srl != find("srl_transitions")? {
+            return Err(LTPError::InvalidInput(String::from(
+                "SRL emissions and transitions must be exported together",
+            )));
+        }
+        Ok(Logits {
+            seg: find("seg_logits")?,
+            pos: find("pos_logits")?,
+            ner: find("ner_logits")?,
+            srl,
+            dep_labels: find("dep_label_logits")?,
+            sdp_labels: find("sdp_label_logits")?,
+        })
+    }
+}
+
+/// The whole pipeline, `LTP` is `Send + Sync` and the inference methods take `&self`, so one
+/// loaded model can be shared by many threads, see [`LTP::new_with_pool_options`].
 pub struct LTP {
     vocabs: Vocab,
     tokenizer: Tokenizer,
-    session: Session<'static>,
+    sessions: SessionPool,
+    signature: Signature,
+    dictionary: Option<Dictionary>,
+    max_length: usize,
+    logits: Logits,
+    confidence: bool,
+    kbest: usize,
+    tree_decoder: TreeDecoder,
+    root_policy: RootPolicy,
+    joint_labels: bool,
+    sdp_policy: SdpPolicy,
 }
 
-macro_rules! option_vec_to_vec_option {
-    ($option_items:expr, $size:expr) => {
-        $option_items.map_or(vec![None; $size], |items| {
-            items.into_iter().map(|item| Some(item)).collect_vec()
-        })
+macro_rules! fill_results {
+    ($results:expr, $field:ident, $items:expr) => {
+        if let Some(items) = $items {
+            for (result, item) in $results.iter_mut().zip(items) {
+                result.$field = Some(item);
+            }
+        }
     };
 }
 
-impl LTP {
-    pub fn new<S: AsRef<OsStr> + ?Sized>(path: &S, num_threads: i16) -> Result<LTP> {
-        LTP::new_with_options(path, GraphOptimizationLevel::All, num_threads)
+/// label ids of an output, and their probabilities when the output holds logits
+macro_rules! extract_ids {
+    ($output:expr, $logits:expr) => {
+        match $logits {
+            true => {
+                let logits: OrtOwnedTensor<f32, _> = $output.try_extract()?;
+                let (ids, probs) = scores::softmax_argmax(&logits.view().into_dyn());
+                (ids, Some(probs))
+            }
+            false => {
+                let ids: OrtOwnedTensor<i64, _> = $output.try_extract()?;
+                (ids.view().into_dyn().to_owned(), None)
+            }
+        }
+    };
+}
+
+impl LTP {
+    pub fn new<S: AsRef<OsStr> + ?Sized>(path: &S, num_threads: i16) -> Result<LTP> {
+        LTP::new_with_options(path, GraphOptimizationLevel::All, num_threads)
+    }
+
+    #[cfg(feature = "cuda")]
+    pub fn new_with_cuda<S: AsRef<OsStr> + ?Sized>(
+        path: &S,
+        num_threads: i16,
+        device_id: i32,
+    ) -> Result<LTP> {
+        LTP::new_with_cuda_options(path, GraphOptimizationLevel::All, num_threads, device_id)
+    }
+
+    pub fn new_with_options<S: AsRef<OsStr> + ?Sized>(
+        path: &S,
+        optimization_level: onnx::GraphOptimizationLevel,
+        num_threads: i16,
+    ) -> Result<LTP> {
+        LTP::new_with_pool_options(path, optimization_level, num_threads, 1)
+    }
+
+    /// Load `pool_size` sessions of the model, so that as many threads can run the pipeline
+    /// concurrently. Every session holds its own copy of the weights, the memory taken by the
+    /// model grows with `pool_size` while the other constructors load a single session.
+    pub fn new_with_pool_options<S: AsRef<OsStr> + ?Sized>(
+        path: &S,
+        optimization_level: onnx::GraphOptimizationLevel,
+        num_threads: i16,
+        pool_size: usize,
+    ) -> Result<LTP> {
+        LTP::load(path, pool_size, |onnx| {
+            Ok(G_ENV
+                .new_session_builder()?
+                .with_optimization_level(optimization_level)?
+                .with_number_threads(num_threads)?
+                .with_model_from_file(onnx)?)
+        })
+    }
+
+    #[cfg(feature = "coreml")]
+    pub fn new_with_coreml_options<S: AsRef<OsStr> + ?Sized>(
+        path: &S,
+        optimization_level: onnx::GraphOptimizationLevel,
+        num_threads: i16,
+        flags: OnnxEnumInt,
+    ) -> Result<LTP> {
+        LTP::load(path, 1, |onnx| {
+            Ok(G_ENV
+                .new_session_builder()?
+                .with_optimization_level(optimization_level)?
+                .with_number_threads(num_threads)?
+                .with_coreml(flags)?
+                .with_model_from_file(onnx)?)
+        })
+    }
+
+    #[cfg(feature = "cuda")]
+    pub fn new_with_cuda_options<S: AsRef<OsStr> + ?Sized>(
+        path: &S,
+        optimization_level: onnx::GraphOptimizationLevel,
+        num_threads: i16,
+        device_id: i32,
+    ) -> Result<LTP> {
+        LTP::new_with_cuda_pool_options(path, optimization_level, num_threads, device_id, 1)
+    }
+
+    #[cfg(feature = "cuda")]
+    pub fn new_with_cuda_pool_options<S: AsRef<OsStr> + ?Sized>(
+        path: &S,
+        optimization_level: onnx::GraphOptimizationLevel,
+        num_threads: i16,
+        device_id: i32,
+        pool_size: usize,
+    ) -> Result<LTP> {
+        LTP::load(path, pool_size, |onnx| {
+            Ok(G_ENV
+                .new_session_builder()?
+                .with_optimization_level(optimization_level)?
+                .with_number_threads(num_threads)?
+                .with_cuda(device_id)?
+                .with_model_from_file(onnx)?)
+        })
+    }
+
+    fn load<S, F>(path: &S, pool_size: usize, build: F) -> Result<LTP>
+    where
+        S: AsRef<OsStr> + ?Sized,
+        F: Fn(String) -> Result<Session<'static>>,
+    {
+        let vocabs = Vocab::load(Path::new(path).join("vocab.json").to_str().unwrap())?;
+        let tokenizer = LTPTokenizer::new(Path::new(path).join("vocab.txt").to_str().unwrap());
+        let onnx = String::from(Path::new(path).join("ltp.onnx").to_str().unwrap());
+
+        let sessions = (0..pool_size.max(1))
+            .map(|_| build(onnx.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let signature = Signature::of(&sessions[0]);
+
+        let logits = Logits::of(&signature, &vocabs)?;
+
+        Ok(LTP {
+            vocabs,
+            tokenizer,
+            sessions: SessionPool::new(sessions),
+            signature,
+            dictionary: None,
+            max_length: 512,
+            logits,
+            confidence: false,
+            kbest: 1,
+            tree_decoder: TreeDecoder::default(),
+            root_policy: RootPolicy::default(),
+            joint_labels: false,
+            sdp_policy: SdpPolicy::default(),
+        })
+    }
+
+    /// Labels of the tasks of the model, `None` for the tasks it was not trained on.
+    pub fn vocabs(&self) -> &Vocab {
+        &self.vocabs
+    }
+
+    /// Inputs and outputs of the ONNX model.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Number of sessions able to run concurrently.
+    pub fn pool_size(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Max sequence length of the encoder, `[CLS]` and `[SEP]` included, longer inputs are
+    /// split into windows and stitched back into one result. Defaults to `512`.
+    pub fn set_max_length(&mut self, max_length: usize) {
+        self.max_length = max_length.max(3);
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Constrain the segmentation with a user dictionary, `None` removes it.
+    ///
+    /// The other tasks are decoded on the constrained words, see
+    /// [`LTP::pipeline_batch_words`] for how the outputs of the model words are projected.
+    pub fn set_dictionary(&mut self, dictionary: Option<Dictionary>) {
+        self.dictionary = dictionary;
+    }
+
+    pub fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_ref()
+    }
+
+    /// Fill [`LTPResult::confidence`] for the decoded tasks, see [`Confidence`] for what is
+    /// available. Defaults to `false`.
+    ///
+    /// Fails when no head of the model can be scored, see [`LTP::confidence_tasks`].
+    pub fn set_confidence(&mut self, confidence: bool) -> Result<()> {
+        if confidence && self.confidence_tasks().is_empty() {
+            return Err(LTPError::InvalidInput(String::from(
+                "the model exports no scores to take the confidence from",
+            )));
+        }
+        self.confidence = confidence;
+        Ok(())
+    }
+
+    /// Tasks of the model filling [`LTPResult::confidence`], tagging heads need to export
+    /// logits while dependency arcs and semantic edges are always scored.
+    pub fn confidence_tasks(&self) -> Tasks {
+        let mut tasks = Tasks::empty();
+        tasks.set(Tasks::SEG, self.logits.seg);
+        tasks.set(Tasks::POS, self.logits.pos);
+        tasks.set(Tasks::NER, self.logits.ner);
+        tasks.set(Tasks::SRL, self.logits.srl);
+        tasks.set(Tasks::DEP, self.vocabs.dep.is_some());
+        tasks.set(Tasks::SDP, self.vocabs.sdp.is_some());
+        tasks
+    }
+
+    pub fn confidence(&self) -> bool {
+        self.confidence
+    }
+
+    /// Fill [`LTPResult::kbest`] with up to `k` alternatives, see [`KBest`] for what is
+    /// available. Defaults to `1`, which leaves it `None`.
+    ///
+    /// Models decoding SRL in the graph only export the Viterbi history of the best rows, so
+    /// [`KBest::srl`] stays `None` for them whatever `k` is. Fails for `k > 1` when the model
+    /// exports none of the logits needed, see [`LTP::kbest_tasks`].
+    pub fn set_kbest(&mut self, k: usize) -> Result<()> {
+        if k > 1 && self.kbest_tasks().is_empty() {
+            return Err(LTPError::InvalidInput(String::from(
+                "the model exports no logits to take alternatives from",
+            )));
+        }
+        self.kbest = k.max(1);
+        Ok(())
+    }
+
+    /// Tasks of the model filling [`LTPResult::kbest`], the ones whose head exports logits.
+    pub fn kbest_tasks(&self) -> Tasks {
+        let mut tasks = Tasks::empty();
+        tasks.set(Tasks::SEG, self.logits.seg);
+        tasks.set(Tasks::SRL, self.logits.srl);
+        tasks.set(Tasks::DEP, self.logits.dep_labels);
+        tasks.set(Tasks::SDP, self.logits.sdp_labels);
+        tasks
+    }
+
+    pub fn kbest(&self) -> usize {
+        self.kbest
+    }
+
+    /// Decoder of the dependency trees and of the backbones of the semantic graphs. Defaults
+    /// to [`TreeDecoder::Projective`].
+    pub fn set_tree_decoder(&mut self, tree_decoder: TreeDecoder) {
+        self.tree_decoder = tree_decoder;
+    }
+
+    pub fn tree_decoder(&self) -> TreeDecoder {
+        self.tree_decoder
+    }
+
+    /// How many words the dependency trees and the backbones of the semantic graphs attach to
+    /// the root. Defaults to [`RootPolicy::Single`].
+    pub fn set_root_policy(&mut self, root_policy: RootPolicy) {
+        self.root_policy = root_policy;
+    }
+
+    pub fn root_policy(&self) -> RootPolicy {
+        self.root_policy
+    }
+
+    /// Score dependency arcs and semantic edges together with their relations, as the arc
+    /// score plus the log probability of the best relation, so that arcs whose relation is
+    /// uncertain are less likely kept. Only for models exporting relation logits, fails when
+    /// the model exports none, heads without them keep their arc scores. Defaults to `false`.
+    ///
+    /// Semantic edges are still kept by the threshold of their own score and the backbone is
+    /// decoded from the same, the joint scores only rank the heads of a word for the other
+    /// options of the [`SdpPolicy`].
+    pub fn set_joint_labels(&mut self, joint_labels: bool) -> Result<()> {
+        if joint_labels && !self.logits.dep_labels && !self.logits.sdp_labels {
+            return Err(LTPError::InvalidInput(String::from(
+                "the model exports no relation logits to score arcs with",
+            )));
+        }
+        self.joint_labels = joint_labels;
+        Ok(())
+    }
+
+    pub fn joint_labels(&self) -> bool {
+        self.joint_labels
+    }
+
+    /// Which edges make up the semantic graphs, see [`SdpPolicy`].
+    pub fn set_sdp_policy(&mut self, sdp_policy: SdpPolicy) {
+        self.sdp_policy = sdp_policy;
+    }
+
+    pub fn sdp_policy(&self) -> &SdpPolicy {
+        &self.sdp_policy
+    }
+
+    fn pipeline_batch_seg(
+        seg: &ArrayViewD<i64>,
+        vocab: &[String],
+        offsets: &[&[(usize, usize)]],
+        sentence_lengths: &[usize],
+    ) -> Vec<Vec<(usize, usize)>> {
+        sentence_lengths
+            .iter()
+            .enumerate()
+            .map(|(idx, &length)| {
+                get_entities(
+                    seg.slice(s![idx, ..length])
+                        .iter()
+                        .filter(|&&x| x != -1)
+                        .map(|&x| vocab[x as usize].as_str())
+                        .collect(),
+                )
+                .iter()
+                .map(|&(_tag, start, end)| (offsets[idx][start + 1].0, offsets[idx][end + 1].1))
+                .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_seg_kbest(
+        log_probs: &ArrayViewD<f32>,
+        vocab: &[String],
+        sentences: &[String],
+        offsets: &[&[(usize, usize)]],
+        sentence_lengths: &[usize],
+        k: usize,
+    ) -> Vec<Vec<Scored>> {
+        let transitions = allowed_transitions(vocab)
+            .into_iter()
+            .map(|allowed| if allowed { 0.0 } else { f32::NEG_INFINITY })
+            .collect_vec();
+        sentence_lengths
+            .iter()
+            .enumerate()
+            .map(|(idx, &length)| {
+                let chars = sentences[idx].chars().collect_vec();
+                let emissions = log_probs
+                    .slice(s![idx, ..length, ..])
+                    .iter()
+                    .cloned()
+                    .collect_vec();
+                viterbi_decode_kbest(
+                    &emissions,
+                    &transitions,
+                    None,
+                    None,
+                    &[length],
+                    vocab.len(),
+                    k,
+                )
+                .remove(0)
+                .into_iter()
+                .map(|(path, score)| {
+                    let tags = path.iter().map(|&x| vocab[x].as_str()).collect();
+                    let words = get_entities(tags)
+                        .iter()
+                        .map(|&(_tag, start, end)| {
+                            let (start, end) = (offsets[idx][start + 1].0, offsets[idx][end + 1].1);
+                            chars[start..end].iter().collect()
+                        })
+                        .collect_vec();
+                    (words, score)
+                })
+                .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_seg_confidence(
+        probs: &ArrayViewD<f32>,
+        seg_entities: &[Vec<(usize, usize)>],
+        offsets: &[&[(usize, usize)]],
+        sentence_lengths: &[usize],
+    ) -> Vec<Vec<f32>> {
+        seg_entities
+            .iter()
+            .enumerate()
+            .map(|(idx, words)| {
+                let tokens = &offsets[idx][1..sentence_lengths[idx] + 1];
+                words
+                    .iter()
+                    .map(|&(start, end)| {
+                        tokens
+                            .iter()
+                            .enumerate()
+                            .filter(|(_token, offset)| offset.0 >= start && offset.1 <= end)
+                            .map(|(token, _offset)| probs[[idx, token]])
+                            .product()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    /// Decode the segmentation again with every dictionary match forced to be a single word,
+    /// the best path of the `emissions` that starts a word at the first token of every match,
+    /// continues it over the others and starts another word right after the match.
+    fn pipeline_batch_constrain(
+        dict: &Dictionary,
+        emissions: &ArrayViewD<f32>,
+        vocab: &[String],
+        sentences: &[String],
+        offsets: &[&[(usize, usize)]],
+        sentence_lengths: &[usize],
+    ) -> Vec<Vec<(usize, usize)>> {
+        let transitions = allowed_transitions(vocab)
+            .into_iter()
+            .map(|allowed| if allowed { 0.0 } else { f32::NEG_INFINITY })
+            .collect_vec();
+        let starts = vocab
+            .iter()
+            .map(|tag| tag.starts_with('B') || tag.starts_with('S'))
+            .collect_vec();
+        sentence_lengths
+            .iter()
+            .enumerate()
+            .map(|(idx, &length)| {
+                let chars = sentences[idx].chars().collect_vec();
+                let tokens = &offsets[idx][1..length + 1];
+                let mut emissions = emissions
+                    .slice(s![idx, ..length, ..])
+                    .iter()
+                    .cloned()
+                    .collect_vec();
+                let mut force = |token: usize, start: bool| {
+                    for (label, &starts) in starts.iter().enumerate() {
+                        if starts != start {
+                            emissions[token * vocab.len() + label] = f32::NEG_INFINITY;
+                        }
+                    }
+                };
+                // a match splitting a token can not be a word of its own
+                for (start, end) in dict.matches(&chars) {
+                    let first = tokens.iter().position(|token| token.0 == start);
+                    let last = tokens.iter().position(|token| token.1 == end);
+                    if let (Some(first), Some(last)) = (first, last) {
+                        force(first, true);
+                        for token in first + 1..=last {
+                            force(token, false);
+                        }
+                        if last + 1 < length {
+                            force(last + 1, true);
+                        }
+                    }
+                }
+                let (path, _score) =
+                    viterbi_decode(&emissions, &transitions, None, None, &[length], vocab.len())
+                        .remove(0);
+                get_entities(path.iter().map(|&x| vocab[x].as_str()).collect())
+                    .iter()
+                    .map(|&(_tag, start, end)| (tokens[start].0, tokens[end].1))
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    /// whether the chunks of `vocab` are tagged BIOES rather than BIO
+    fn bioes(vocab: &[String]) -> bool {
+        vocab
+            .iter()
+            .any(|tag| tag.starts_with("S-") || tag.starts_with("E-"))
+    }
+
+    fn pipeline_batch_tags(
+        tags: &ArrayViewD<i64>,
+        vocab: &[String],
+        word_nums: &[usize],
+    ) -> Vec<Vec<String>> {
+        word_nums
+            .iter()
+            .enumerate()
+            .map(|(batch_idx, &length)| {
+                tags.slice(s![batch_idx, ..length])
+                    .iter()
+                    .filter(|&&x| x != -1)
+                    .map(|&x| vocab[x as usize].clone())
+                    .collect()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_tags_confidence(
+        probs: &ArrayViewD<f32>,
+        word_nums: &[usize],
+    ) -> Vec<Vec<f32>> {
+        word_nums
+            .iter()
+            .enumerate()
+            .map(|(batch_idx, &length)| probs.slice(s![batch_idx, ..length]).to_vec())
+            .collect_vec()
+    }
+
+    fn pipeline_batch_entities(
+        ner: &[Vec<String>],
+        spans: &[Vec<(usize, usize)>],
+        sentences: &[String],
+    ) -> Vec<Vec<Entity>> {
+        ner.iter()
+            .zip(spans)
+            .zip(sentences)
+            .map(|((tags, spans), sentence)| {
+                Entity::from_tags(tags, spans, &sentence.chars().collect_vec())
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_srl(
+        srl_history: &[i64],
+        srl_last_tags: &[i64],
+        vocab: &[String],
+        word_nums: &[usize],
+    ) -> Vec<Vec<Vec<String>>> {
+        let mut all_srl =
+            viterbi_decode_postprocess(srl_history, srl_last_tags, word_nums, vocab.len())
+                .iter()
+                .map(|stn_srl| {
+                    stn_srl
+                        .iter()
+                        .map(|tag| vocab[*tag as usize].clone())
+                        .collect_vec()
+                })
+                .collect_vec();
+
+        let mut results = Vec::new();
+        for stn_len in word_nums {
+            let mut result = Vec::new();
+            for _i in 0..*stn_len {
+                result.push(all_srl.remove(0))
+            }
+            results.push(result)
+        }
+        results
+    }
+
+    fn pipeline_batch_dep(
+        dep_head: &[f32],
+        dep_labels: &ArrayViewD<i64>,
+        vocab: &[String],
+        cls_word_num: &[usize],
+        tree_decoder: TreeDecoder,
+        root_policy: RootPolicy,
+    ) -> Vec<Vec<DEP>> {
+        let dep_head_decoded = tree_decoder.decode(dep_head, cls_word_num, true, root_policy);
+        dep_head_decoded
+            .iter()
+            .enumerate()
+            .map(|(batch, stn_head)| {
+                stn_head
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &head)| DEP {
+                        arc: head,
+                        rel: vocab[dep_labels[[batch, idx + 1, head]] as usize].clone(),
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    /// transitions of the SRL CRF, and the start and end scores when the model stacks them
+    /// as two extra rows
+    fn srl_transitions(
+        transitions: &[f32],
+        labels_num: usize,
+    ) -> (&[f32], Option<&[f32]>, Option<&[f32]>) {
+        let block_size = labels_num * labels_num;
+        match transitions.len() == block_size + 2 * labels_num {
+            true => (
+                &transitions[..block_size],
+                Some(&transitions[block_size..block_size + labels_num]),
+                Some(&transitions[block_size + labels_num..]),
+            ),
+            false => (transitions, None, None),
+        }
+    }
+
+    /// every word is a predicate tagging the words of its sentence, the emissions are
+    /// `[batch, predicate, word, label]` so padded predicates are empty sequences
+    fn srl_lengths(word_nums: &[usize]) -> Vec<usize> {
+        let max_word_num = word_nums.iter().cloned().max().unwrap_or(0);
+        word_nums
+            .iter()
+            .flat_map(|&length| {
+                (0..max_word_num).map(move |predicate| match predicate < length {
+                    true => length,
+                    false => 0,
+                })
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_srl_crf(
+        emissions: &[f32],
+        transitions: &[f32],
+        vocab: &[String],
+        word_nums: &[usize],
+        k: usize,
+    ) -> Vec<Vec<Vec<Scored>>> {
+        let (transitions, start, end) = Self::srl_transitions(transitions, vocab.len());
+        let stn_lengths = Self::srl_lengths(word_nums);
+        let decoded = match k {
+            1 => viterbi_decode(
+                emissions,
+                transitions,
+                start,
+                end,
+                &stn_lengths,
+                vocab.len(),
+            )
+            .into_iter()
+            .map(|best| vec![best])
+            .collect_vec(),
+            _ => viterbi_decode_kbest(
+                emissions,
+                transitions,
+                start,
+                end,
+                &stn_lengths,
+                vocab.len(),
+                k,
+            ),
+        };
+
+        let max_word_num = word_nums.iter().cloned().max().unwrap_or(0);
+        let mut decoded = decoded.into_iter();
+        word_nums
+            .iter()
+            .map(|&length| {
+                let predicates = decoded.by_ref().take(max_word_num).collect_vec();
+                predicates
+                    .into_iter()
+                    .take(length)
+                    .map(|paths| {
+                        paths
+                            .into_iter()
+                            .map(|(path, score)| {
+                                let tags = path.iter().map(|&x| vocab[x].clone()).collect_vec();
+                                (tags, score)
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_srl_confidence(
+        emissions: &[f32],
+        transitions: &[f32],
+        labels_num: usize,
+        candidates: &[Vec<Vec<Scored>>],
+        word_nums: &[usize],
+    ) -> Vec<Vec<f32>> {
+        let (transitions, start, end) = Self::srl_transitions(transitions, labels_num);
+        let stn_lengths = Self::srl_lengths(word_nums);
+        let log_partitions =
+            crf_forward(emissions, transitions, start, end, &stn_lengths, labels_num);
+
+        let max_word_num = word_nums.iter().cloned().max().unwrap_or(0);
+        candidates
+            .iter()
+            .enumerate()
+            .map(|(batch, predicates)| {
+                predicates
+                    .iter()
+                    .enumerate()
+                    .map(|(predicate, paths)| {
+                        let log_partition = log_partitions[batch * max_word_num + predicate];
+                        (paths[0].1 - log_partition).exp()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    /// arc scores plus the log probability of the best relation of every arc
+    fn joint_arc_scores(head: &[f32], label_probs: &ArrayD<f32>) -> Vec<f32> {
+        head.iter()
+            .zip(label_probs.iter())
+            .map(|(&score, &prob)| score + prob.ln())
+            .collect_vec()
+    }
+
+    /// up to `k` relations of every arc best first, arcs are `(word, head)` in the label logits
+    fn pipeline_batch_labels_kbest(
+        label_logits: &ArrayViewD<f32>,
+        vocab: &[String],
+        arcs: &[Vec<(usize, usize)>],
+        k: usize,
+    ) -> Vec<Vec<Vec<(String, f32)>>> {
+        arcs.iter()
+            .enumerate()
+            .map(|(batch, arcs)| {
+                arcs.iter()
+                    .map(|&(word, head)| {
+                        let logits = label_logits.slice(s![batch, word, head, ..]).to_vec();
+                        scores::softmax_top_k(&logits, k)
+                            .into_iter()
+                            .map(|(label, prob)| (vocab[label].clone(), prob))
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_dep_confidence(
+        dep_head: &[f32],
+        label_probs: Option<&ArrayD<f32>>,
+        dep: &[Vec<DEP>],
+        cls_word_num: &[usize],
+    ) -> Vec<Vec<f32>> {
+        let &max_cls_stn_length = cls_word_num.iter().max().unwrap();
+        let block_size = max_cls_stn_length * max_cls_stn_length;
+        dep.iter()
+            .enumerate()
+            .map(|(batch, stn_dep)| {
+                stn_dep
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, x)| {
+                        let row = batch * block_size + (idx + 1) * max_cls_stn_length;
+                        let heads = &dep_head[row..row + cls_word_num[batch]];
+                        let label = label_probs.map_or(1.0, |probs| probs[[batch, idx + 1, x.arc]]);
+                        scores::softmax_at(heads, x.arc) * label
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_sdp_confidence(
+        sdp_head: &[f32],
+        label_probs: Option<&ArrayD<f32>>,
+        sdp: &[Vec<SDP>],
+        cls_word_num: &[usize],
+    ) -> Vec<Vec<f32>> {
+        let &max_cls_stn_length = cls_word_num.iter().max().unwrap();
+        let block_size = max_cls_stn_length * max_cls_stn_length;
+        sdp.iter()
+            .enumerate()
+            .map(|(batch, edges)| {
+                edges
+                    .iter()
+                    .map(|x| {
+                        let idx = batch * block_size + x.src * max_cls_stn_length + x.tgt;
+                        let label = label_probs.map_or(1.0, |probs| probs[[batch, x.src, x.tgt]]);
+                        scores::sigmoid(sdp_head[idx]) * label
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn pipeline_batch_sdp(
+        &self,
+        sdp_head: &[f32],
+        joint_head: Option<&[f32]>,
+        sdp_labels: &ArrayViewD<i64>,
+        vocab: &[String],
+        cls_word_num: &[usize],
+    ) -> Vec<Vec<SDP>> {
+        let backbone = match self.sdp_policy.backbone {
+            true => Some(
+                self.tree_decoder
+                    .decode(sdp_head, cls_word_num, false, self.root_policy),
+            ),
+            false => None,
+        };
+        // 这里要考虑虚节点
+        sdp::decode(
+            sdp_head,
+            joint_head,
+            cls_word_num,
+            backbone.as_deref(),
+            &self.sdp_policy,
+        )
+        .into_iter()
+        .enumerate()
+        .map(|(batch, edges)| {
+            edges
+                .into_iter()
+                .map(|(current, target)| SDP {
+                    src: current,
+                    tgt: target,
+                    rel: vocab[sdp_labels[[batch, current, target]] as usize].clone(),
+                })
+                .collect_vec()
+        })
+        .collect_vec()
     }
 
-    #[cfg(feature = "cuda")]
-    pub fn new_with_cuda<S: AsRef<OsStr> + ?Sized>(
-        path: &S,
-        num_threads: i16,
-        device_id: i32,
-    ) -> Result<LTP> {
-        LTP::new_with_cuda_options(path, GraphOptimizationLevel::All, num_threads, device_id)
+    pub fn pipeline_batch(&self, sentences: &Vec<String>) -> Result<Vec<LTPResult>> {
+        self.pipeline_batch_with(sentences, Tasks::all())
     }
 
-    pub fn new_with_options<S: AsRef<OsStr> + ?Sized>(
-        path: &S,
-        optimization_level: onnx::GraphOptimizationLevel,
-        num_threads: i16,
-    ) -> Result<LTP> {
-        let vocabs = Vocab::load(Path::new(path).join("vocab.json").to_str().unwrap())?;
-        let tokenizer = LTPTokenizer::new(Path::new(path).join("vocab.txt").to_str().unwrap());
-        let onnx = String::from(Path::new(path).join("ltp.onnx").to_str().unwrap());
+    /// Run the pipeline but only decode the `tasks` asked for, the fields of skipped tasks are
+    /// left `None`. Segmentation is always decoded internally when any word level task is
+    /// requested, since word boundaries are needed to slice the other outputs.
+    ///
+    /// Outputs of skipped tasks are dropped without being extracted or post-processed.
+    pub fn pipeline_batch_with(
+        &self,
+        sentences: &Vec<String>,
+        tasks: Tasks,
+    ) -> Result<Vec<LTPResult>> {
+        if self.vocabs.seg.is_none() {
+            return Err(LTPError::InvalidInput(String::from(
+                "the model has no segmentation head",
+            )));
+        }
 
-        let session = G_ENV
-            .new_session_builder()?
-            .with_optimization_level(optimization_level)?
-            .with_number_threads(num_threads)?
-            .with_model_from_file(onnx)?;
+        // inputs longer than the encoder are split into windows on sentence or clause
+        // boundaries, by chars which the tokenizer is checked to never split into more tokens
+        let windows = sentences
+            .iter()
+            .map(|sentence| {
+                let length = sentence.chars().count();
+                if length <= self.max_length - 2 {
+                    return vec![(0, length)];
+                }
+                let mut levels = sentence.chars().map(window::boundary_level).collect_vec();
+                for one in split_sentences(sentence) {
+                    levels[one.char_offsets.1 - 1] = 2;
+                }
+                window::split(&vec![1; levels.len()], &levels, self.max_length - 2)
+            })
+            .collect_vec();
+        if windows.iter().all(|windows| windows.len() == 1) {
+            let results = self.pipeline_batch_inner(sentences, None, tasks)?;
+            return Ok(results.into_iter().map(|(result, _)| result).collect_vec());
+        }
 
-        Ok(LTP {
-            vocabs,
-            tokenizer,
-            session,
-        })
+        let mut texts = Vec::new();
+        let mut biases = Vec::new();
+        for (sentence, windows) in sentences.iter().zip(&windows) {
+            let chars = sentence.chars().collect_vec();
+            let bytes = sentence
+                .char_indices()
+                .map(|(byte, _char)| byte)
+                .chain(std::iter::once(sentence.len()))
+                .collect_vec();
+            for &(start, end) in windows {
+                texts.push(chars[start..end].iter().collect());
+            }
+            biases.push(
+                windows
+                    .iter()
+                    .map(|&(start, _end)| (start, bytes[start]))
+                    .collect_vec(),
+            );
+        }
+        let results = self.pipeline_batch_inner(&texts, None, tasks)?;
+        Ok(Self::stitch(results, &biases))
     }
 
-    #[cfg(feature = "coreml")]
-    pub fn new_with_coreml_options<S: AsRef<OsStr> + ?Sized>(
-        path: &S,
-        optimization_level: onnx::GraphOptimizationLevel,
-        num_threads: i16,
-        flags: OnnxEnumInt,
-    ) -> Result<LTP> {
-        let vocabs = Vocab::load(Path::new(path).join("vocab.json").to_str().unwrap())?;
-        let tokenizer = LTPTokenizer::new(Path::new(path).join("vocab.txt").to_str().unwrap());
-        let onnx = String::from(Path::new(path).join("ltp.onnx").to_str().unwrap());
+    /// Run the pipeline on pre-segmented input, the downstream tasks are decoded on the given
+    /// words.
+    ///
+    /// The words are tokenized apart from each other and the model still pools its word level
+    /// outputs from its own segmentation, they are projected onto the given words by overlap:
+    /// tags come from the model word sharing the most chars with a word, entities and SRL
+    /// arguments are widened to the words they overlap, and the trees and graphs are decoded
+    /// on the best scoring arcs between the model words of every two words.
+    pub fn pipeline_batch_words(
+        &self,
+        words: &[Vec<String>],
+        tasks: Tasks,
+    ) -> Result<Vec<LTPResult>> {
+        if self.vocabs.seg.is_none() {
+            return Err(LTPError::InvalidInput(String::from(
+                "the model has no segmentation head to align the words with",
+            )));
+        }
+        if words.iter().flatten().any(|word| word.trim().is_empty()) {
+            return Err(LTPError::InvalidInput(String::from(
+                "words can not be empty or whitespace",
+            )));
+        }
+
+        let windows = words
+            .iter()
+            .map(|words| {
+                let lengths = words.iter().map(|word| word.chars().count()).collect_vec();
+                let levels = words
+                    .iter()
+                    .map(|word| word.chars().last().map_or(0, window::boundary_level))
+                    .collect_vec();
+                window::split(&lengths, &levels, self.max_length - 2)
+            })
+            .collect_vec();
 
-        let session = G_ENV
-            .new_session_builder()?
-            .with_optimization_level(optimization_level)?
-            .with_number_threads(num_threads)?
-            .with_coreml(flags)?
-            .with_model_from_file(onnx)?;
+        let mut windowed = Vec::new();
+        let mut biases = Vec::new();
+        for (words, windows) in words.iter().zip(&windows) {
+            let mut sentence_biases = Vec::new();
+            for &(start, end) in windows {
+                let char_bias: usize = words[..start].iter().map(|w| w.chars().count()).sum();
+                let byte_bias: usize = words[..start].iter().map(|w| w.len()).sum();
+                sentence_biases.push((char_bias, byte_bias));
+                windowed.push(words[start..end].to_vec());
+            }
+            biases.push(sentence_biases);
+        }
 
-        Ok(LTP {
-            vocabs,
-            tokenizer,
-            session,
-        })
+        // words are joined by spaces so that no token crosses them, the offsets are brought
+        // back to the concatenated words afterwards
+        let sentences = windowed.iter().map(|words| words.join(" ")).collect_vec();
+        let spans = windowed
+            .iter()
+            .map(|words| Self::word_spans(words, 1))
+            .collect_vec();
+        let mut results = self.pipeline_batch_inner(&sentences, Some(spans), tasks)?;
+        for ((result, _), words) in results.iter_mut().zip(&windowed) {
+            Self::concat_offsets(result, words);
+        }
+        Ok(Self::stitch(results, &biases))
     }
 
-    #[cfg(feature = "cuda")]
-    pub fn new_with_cuda_options<S: AsRef<OsStr> + ?Sized>(
-        path: &S,
-        optimization_level: onnx::GraphOptimizationLevel,
-        num_threads: i16,
-        device_id: i32,
-    ) -> Result<LTP> {
-        let vocabs = Vocab::load(Path::new(path).join("vocab.json").to_str().unwrap())?;
-        let tokenizer = LTPTokenizer::new(Path::new(path).join("vocab.txt").to_str().unwrap());
-        let onnx = String::from(Path::new(path).join("ltp.onnx").to_str().unwrap());
+    /// Stitch the results of the windows back together, with the word count of every window,
+    /// `biases` holds the char and byte offsets of every window of every input.
+    fn stitch(results: Vec<(LTPResult, usize)>, biases: &[Vec<(usize, usize)>]) -> Vec<LTPResult> {
+        let mut results = results.into_iter();
+        biases
+            .iter()
+            .map(|biases| {
+                let (mut result, mut word_bias) = results.next().unwrap();
+                for &(char_bias, byte_bias) in &biases[1..] {
+                    let (other, other_word_num) = results.next().unwrap();
+                    result.extend(other, word_bias, other_word_num, char_bias, byte_bias);
+                    word_bias += other_word_num;
+                }
+                result
+            })
+            .collect_vec()
+    }
 
-        let session = G_ENV
-            .new_session_builder()?
-            .with_optimization_level(optimization_level)?
-            .with_number_threads(num_threads)?
-            .with_cuda(device_id)?
-            .with_model_from_file(onnx)?;
+    /// char offsets of the words once they are joined by `sep` chars
+    fn word_spans(words: &[String], sep: usize) -> Vec<(usize, usize)> {
+        words
+            .iter()
+            .scan(0, |start, word| {
+                let end = *start + word.chars().count();
+                let span = (*start, end);
+                *start = end + sep;
+                Some(span)
+            })
+            .collect_vec()
+    }
 
-        Ok(LTP {
-            vocabs,
-            tokenizer,
-            session,
-        })
+    /// move the offsets of a result on the space joined `words` to the concatenated words
+    fn concat_offsets(result: &mut LTPResult, words: &[String]) {
+        let spans = Self::word_spans(words, 0);
+        if let Some(char_offsets) = result.char_offsets.as_mut() {
+            *char_offsets = spans.clone();
+        }
+        if let Some(byte_offsets) = result.byte_offsets.as_mut() {
+            *byte_offsets = words
+                .iter()
+                .scan(0, |start, word| {
+                    let span = (*start, *start + word.len());
+                    *start = span.1;
+                    Some(span)
+                })
+                .collect_vec();
+        }
+        for entity in result.entities.iter_mut().flatten() {
+            entity.text = words[entity.start_word..=entity.end_word].concat();
+            entity.char_start = spans[entity.start_word].0;
+            entity.char_end = spans[entity.end_word].1;
+        }
     }
 
-    fn pipeline_batch_seg(&mut self) {}
-    fn pipeline_batch_pos(&mut self) {}
-    fn pipeline_batch_ner(&mut self) {}
-    fn pipeline_batch_srl(&mut self) {}
-    fn pipeline_batch_dep(&mut self) {}
-    fn pipeline_batch_sdp(&mut self) {}
+    /// per sentence outputs on the model words projected onto the words of the results
+    fn project<T, F: Fn(&Alignment, &T) -> T>(
+        outputs: Vec<T>,
+        alignments: Option<&Vec<Alignment>>,
+        project: F,
+    ) -> Vec<T> {
+        match alignments {
+            Some(alignments) => outputs
+                .iter()
+                .zip(alignments)
+                .map(|(output, alignment)| project(alignment, output))
+                .collect_vec(),
+            None => outputs,
+        }
+    }
+
+    /// rows of the model predicates as rows of the given words, words not taken as predicate
+    /// get `empty`
+    fn project_rows<T: Clone>(
+        alignment: &Alignment,
+        rows: &[T],
+        empty: T,
+        project: impl Fn(&T) -> T,
+    ) -> Vec<T> {
+        (0..alignment.len())
+            .map(|word| match alignment.predicate(word) {
+                Some(predicate) => project(&rows[predicate]),
+                None => empty.clone(),
+            })
+            .collect_vec()
+    }
 
-    // todo: code is too long, need to split and refactor to support different models
-    pub fn pipeline_batch(&mut self, sentences: &Vec<String>) -> Result<Vec<LTPResult>> {
+    fn pipeline_batch_inner(
+        &self,
+        sentences: &Vec<String>,
+        words: Option<Vec<Vec<(usize, usize)>>>,
+        tasks: Tasks,
+    ) -> Result<Vec<(LTPResult, usize)>> {
         let inputs = sentences
             .iter()
             .map(|s| EncodeInput::Single(s.to_string()))
@@ -201,6 +1606,17 @@ impl LTP {
                 len
             })
             .collect_vec();
+        // windows are cut by chars, they only fit when no char is split into several tokens
+        if let Some(&length) = sentence_lengths
+            .iter()
+            .find(|&&length| length + 2 > self.max_length)
+        {
+            return Err(LTPError::InvalidInput(format!(
+                "an input of {} tokens is longer than the max length {}",
+                length + 2,
+                self.max_length
+            )));
+        }
         let input_arrays = vec![
             ArrayBase::from_shape_vec((batch_size, sentence_length), input_ids)?,
             ArrayBase::from_shape_vec((batch_size, sentence_length), attention_mask)?,
@@ -208,264 +1624,749 @@ impl LTP {
             ArrayBase::from_shape_vec((batch_size, sentence_length), position_ids)?,
         ];
 
-        let mut result = self.session.run(input_arrays)?;
+        let mut session = self.sessions.acquire();
+        let mut result = session.run(input_arrays)?;
 
-        let seg = result.remove(0);
-        let seg: OrtOwnedTensor<i64, _> = seg.try_extract()?;
+        let mut seg_probs = None;
+        let mut seg_kbest = None;
+        // the words the model pooled its word level outputs from
+        let mut dict_entities = None;
+        let model_entities = match self.vocabs.seg.as_ref() {
+            Some(vocab) => {
+                let seg = result.remove(0);
+                match tasks.is_empty() {
+                    false => {
+                        let dict = self.dictionary.as_ref().filter(|_| words.is_none());
+                        let seg = match self.logits.seg {
+                            true => {
+                                let logits: OrtOwnedTensor<f32, _> = seg.try_extract()?;
+                                let logits = logits.view().into_dyn();
+                                let (seg, probs) = scores::softmax_argmax(&logits);
+                                // the model tags only describe the words it segmented itself
+                                let own_words = words.is_none() && dict.is_none();
+                                let kbest =
+                                    self.kbest > 1 && own_words && tasks.contains(Tasks::SEG);
+                                if kbest || dict.is_some() {
+                                    let log_probs = scores::log_softmax(&logits);
+                                    if kbest {
+                                        seg_kbest = Some(Self::pipeline_batch_seg_kbest(
+                                            &log_probs.view(),
+                                            vocab,
+                                            sentences,
+                                            &offsets,
+                                            &sentence_lengths,
+                                            self.kbest,
+                                        ));
+                                    }
+                                    dict_entities = dict.map(|dict| {
+                                        Self::pipeline_batch_constrain(
+                                            dict,
+                                            &log_probs.view(),
+                                            vocab,
+                                            sentences,
+                                            &offsets,
+                                            &sentence_lengths,
+                                        )
+                                    });
+                                }
+                                seg_probs = Some(probs).filter(|_| own_words);
+                                seg
+                            }
+                            false => {
+                                let seg: OrtOwnedTensor<i64, _> = seg.try_extract()?;
+                                let seg = seg.view().into_dyn().to_owned();
+                                // without logits the model tags score 0 and the others -1
+                                dict_entities = dict.map(|dict| {
+                                    let shape = [seg.shape()[0], seg.shape()[1], vocab.len()];
+                                    let emissions = ArrayD::from_shape_fn(IxDyn(&shape), |idx| {
+                                        match seg[[idx[0], idx[1]]] == idx[2] as i64 {
+                                            true => 0.0,
+                                            false => -1.0,
+                                        }
+                                    });
+                                    Self::pipeline_batch_constrain(
+                                        dict,
+                                        &emissions.view(),
+                                        vocab,
+                                        sentences,
+                                        &offsets,
+                                        &sentence_lengths,
+                                    )
+                                });
+                                seg
+                            }
+                        };
+                        Some(Self::pipeline_batch_seg(
+                            &seg.view(),
+                            vocab,
+                            &offsets,
+                            &sentence_lengths,
+                        ))
+                    }
+                    true => None,
+                }
+            }
+            None => None,
+        };
+        // the words of the results, the given ones or the segmentation constrained by the
+        // dictionary
+        let seg_entities = match (words, dict_entities) {
+            (Some(words), _) => Some(words),
+            (None, Some(dict_entities)) => Some(dict_entities),
+            (None, None) => model_entities.clone(),
+        };
+        let alignments = match (seg_entities.as_ref(), model_entities.as_ref()) {
+            (Some(words), Some(model)) if words != model => {
+                if words
+                    .iter()
+                    .zip(model)
+                    .any(|(words, model)| !words.is_empty() && model.is_empty())
+                {
+                    return Err(LTPError::InvalidInput(String::from(
+                        "the model found no word in the given words",
+                    )));
+                }
+                Some(
+                    words
+                        .iter()
+                        .zip(model)
+                        .map(|(words, model)| Alignment::new(words, model))
+                        .collect_vec(),
+                )
+            }
+            _ => None,
+        };
+        let seg_confidence = match (seg_probs, seg_entities.as_ref()) {
+            (Some(probs), Some(seg_entities)) if self.confidence && tasks.contains(Tasks::SEG) => {
+                Some(Self::pipeline_batch_seg_confidence(
+                    &probs.view(),
+                    seg_entities,
+                    &offsets,
+                    &sentence_lengths,
+                ))
+            }
+            _ => None,
+        };
 
-        let seg_entities: Option<Vec<Vec<(&str, usize, usize)>>> = match self.vocabs.seg.as_ref() {
-            Some(vocab) => Some({
-                (0..batch_size)
-                    .into_iter()
-                    .zip(sentence_lengths)
-                    .map(|(idx, length)| {
-                        get_entities(
-                            seg.slice(s![idx, ..length])
-                                .iter()
-                                .filter(|&&x| x != -1)
-                                .map(|&x| vocab[x as usize].as_str())
-                                .collect(),
-                        )
+        let texts: Option<Vec<Vec<String>>> = match seg_entities.as_ref() {
+            Some(seg_entities) if tasks.contains(Tasks::SEG) => Some(
+                seg_entities
+                    .iter()
+                    .zip(sentences)
+                    .map(|(sent, text)| {
+                        let text = text.chars().collect_vec();
+                        sent.iter()
+                            .map(|&(start, end)| text[start..end].iter().collect())
+                            .collect_vec()
                     })
-                    .collect_vec()
-            }),
-            None => None,
+                    .collect_vec(),
+            ),
+            _ => None,
         };
 
-        let seg_entities: Option<Vec<Vec<(usize, usize)>>> = seg_entities.map(|x| {
-            x.iter()
-                .enumerate()
-                .map(|(idx, sent)| {
-                    sent.iter()
-                        .map(|&(_tag, start, end)| {
-                            (offsets[idx][start + 1].0, offsets[idx][end + 1].1)
-                        })
-                        .collect_vec()
-                })
-                .collect()
-        });
+        let byte_offsets: Option<Vec<Vec<(usize, usize)>>> = match seg_entities.as_ref() {
+            Some(seg_entities) if tasks.contains(Tasks::SEG) => Some(
+                seg_entities
+                    .iter()
+                    .zip(sentences)
+                    .map(|(sent, text)| {
+                        let bytes = text
+                            .char_indices()
+                            .map(|(byte, _char)| byte)
+                            .chain(std::iter::once(text.len()))
+                            .collect_vec();
+                        sent.iter()
+                            .map(|&(start, end)| (bytes[start], bytes[end]))
+                            .collect_vec()
+                    })
+                    .collect_vec(),
+            ),
+            _ => None,
+        };
+        let char_offsets = match tasks.contains(Tasks::SEG) {
+            true => seg_entities.clone(),
+            false => None,
+        };
 
-        let texts = sentences
-            .iter()
-            .map(|x| x.chars().collect_vec())
-            .collect_vec();
-        let texts: Option<Vec<Vec<String>>> = seg_entities.map(|x| {
-            x.iter()
-                .zip(texts)
-                .map(|(sent, text)| {
-                    sent.iter()
-                        .map(|&(start, end)| text[start..end].iter().collect())
-                        .collect_vec()
-                })
-                .collect_vec()
-        });
+        let word_nums = seg_entities
+            .as_ref()
+            .map(|x| x.iter().map(|sent| sent.len()).collect_vec())
+            .unwrap_or_else(|| vec![0; batch_size]);
 
-        let word_nums = texts
+        let model_word_nums = model_entities
             .as_ref()
             .map(|x| x.iter().map(|sent| sent.len()).collect_vec())
-            .unwrap();
+            .unwrap_or_else(|| vec![0; batch_size]);
+        let alignments = alignments.as_ref();
 
-        let pos: Option<Vec<Vec<String>>> = match self.vocabs.pos.as_ref() {
-            Some(vocab) => Some({
+        let mut pos_confidence = None;
+        let pos = match self.vocabs.pos.as_ref() {
+            Some(vocab) => {
                 let pos = result.remove(0);
-                let pos: OrtOwnedTensor<i64, _> = pos.try_extract()?;
-                (0..batch_size)
-                    .into_iter()
-                    .zip(&word_nums)
-                    .map(|(batch_idx, &length)| {
-                        pos.slice(s![batch_idx, ..length])
-                            .iter()
-                            .filter(|&&x| x != -1)
-                            .map(|&x| vocab[x as usize].clone())
-                            .collect()
-                    })
-                    .collect_vec()
-            }),
+                match tasks.contains(Tasks::POS) {
+                    true => {
+                        let (pos, probs) = extract_ids!(pos, self.logits.pos);
+                        pos_confidence = probs.filter(|_| self.confidence).map(|probs| {
+                            let confidence = Self::pipeline_batch_tags_confidence(
+                                &probs.view(),
+                                &model_word_nums,
+                            );
+                            Self::project(confidence, alignments, |x, tags| x.tags(tags))
+                        });
+                        let pos = Self::pipeline_batch_tags(&pos.view(), vocab, &model_word_nums);
+                        Some(Self::project(pos, alignments, |x, tags| x.tags(tags)))
+                    }
+                    false => None,
+                }
+            }
             None => None,
         };
 
-        let ner: Option<Vec<Vec<String>>> = match self.vocabs.ner.as_ref() {
-            Some(vocab) => Some({
+        let mut ner_confidence = None;
+        let ner = match self.vocabs.ner.as_ref() {
+            Some(vocab) => {
                 let ner = result.remove(0);
-                let ner: OrtOwnedTensor<i64, _> = ner.try_extract()?;
-                (0..batch_size)
-                    .into_iter()
-                    .zip(&word_nums)
-                    .map(|(batch_idx, &length)| {
-                        ner.slice(s![batch_idx, ..length])
-                            .iter()
-                            .filter(|&&x| x != -1)
-                            .map(|&x| vocab[x as usize].clone())
-                            .collect()
-                    })
-                    .collect_vec()
-            }),
+                match tasks.contains(Tasks::NER) {
+                    true => {
+                        let (ner, probs) = extract_ids!(ner, self.logits.ner);
+                        ner_confidence = probs.filter(|_| self.confidence).map(|probs| {
+                            let confidence = Self::pipeline_batch_tags_confidence(
+                                &probs.view(),
+                                &model_word_nums,
+                            );
+                            Self::project(confidence, alignments, |x, tags| x.tags(tags))
+                        });
+                        let ner = Self::pipeline_batch_tags(&ner.view(), vocab, &model_word_nums);
+                        let bioes = Self::bioes(vocab);
+                        Some(Self::project(ner, alignments, |x, tags| {
+                            x.chunks(tags, bioes)
+                        }))
+                    }
+                    false => None,
+                }
+            }
             None => None,
         };
+        let entities = match (ner.as_ref(), seg_entities.as_ref()) {
+            (Some(ner), Some(spans)) => Some(Self::pipeline_batch_entities(ner, spans, sentences)),
+            _ => None,
+        };
 
+        let mut srl_kbest = None;
+        let mut srl_confidence = None;
         let srl = match self.vocabs.srl.as_ref() {
-            Some(vocab) => Some({
+            Some(vocab) => {
+                // the Viterbi history and last tags, or the emissions and transitions when the
+                // model leaves the decoding to us
                 let srl_history: DynOrtTensor<_> = result.remove(0);
-                let srl_last_tags: OrtOwnedTensor<i64, _> = result.remove(0).try_extract()?;
-
-                match srl_history.is_valid() {
-                    true => {
-                        let srl_history: OrtOwnedTensor<i64, _> = srl_history.try_extract()?;
-
-                        viterbi_decode_postprocess(
-                            srl_history.as_slice().unwrap(),
-                            srl_last_tags.as_slice().unwrap(),
-                            word_nums.as_slice(),
-                            vocab.len(),
-                        )
-                        .iter()
-                        .map(|stn_srl| {
-                            stn_srl
-                                .iter()
-                                .map(|tag| vocab[*tag as usize].clone())
-                                .collect_vec()
-                        })
-                        .collect_vec()
+                let srl_last_tags = result.remove(0);
+                let bioes = Self::bioes(vocab);
+                match (tasks.contains(Tasks::SRL), self.logits.srl) {
+                    (true, true) => {
+                        let emissions: OrtOwnedTensor<f32, _> = srl_history.try_extract()?;
+                        let transitions: OrtOwnedTensor<f32, _> = srl_last_tags.try_extract()?;
+                        let candidates = Self::pipeline_batch_srl_crf(
+                            emissions.as_slice().unwrap(),
+                            transitions.as_slice().unwrap(),
+                            vocab,
+                            &model_word_nums,
+                            self.kbest,
+                        );
+                        let confidence = match self.confidence {
+                            true => Some(Self::pipeline_batch_srl_confidence(
+                                emissions.as_slice().unwrap(),
+                                transitions.as_slice().unwrap(),
+                                vocab.len(),
+                                &candidates,
+                                &model_word_nums,
+                            )),
+                            false => None,
+                        };
+                        let candidates = Self::project(candidates, alignments, |x, rows| {
+                            Self::project_rows(x, rows, vec![], |paths| {
+                                paths
+                                    .iter()
+                                    .map(|(tags, score)| (x.chunks(tags, bioes), *score))
+                                    .collect_vec()
+                            })
+                        });
+                        let srl = candidates
+                            .iter()
+                            .zip(&word_nums)
+                            .map(|(predicates, &word_num)| {
+                                let empty = vec![String::from("O"); word_num];
+                                let rows = predicates
+                                    .iter()
+                                    .map(|paths| paths.first().map_or(&empty, |x| &x.0))
+                                    .collect_vec();
+                                SrlFrame::from_rows(&rows)
+                            })
+                            .collect_vec();
+                        if let Some(confidence) = confidence {
+                            let confidence = Self::project(confidence, alignments, |x, rows| {
+                                Self::project_rows(x, rows, 0.0, |&score| score)
+                            });
+                            srl_confidence = Some(
+                                confidence
+                                    .iter()
+                                    .zip(&srl)
+                                    .map(|(rows, frames)| {
+                                        frames.iter().map(|x| rows[x.predicate]).collect_vec()
+                                    })
+                                    .collect_vec(),
+                            );
+                        }
+                        if self.kbest > 1 {
+                            srl_kbest = Some(candidates);
+                        }
+                        Some(srl)
                     }
-                    false => {
-                        let srl_last_tags = srl_last_tags.as_slice().unwrap_or(&[0]);
-                        let res = vocab[srl_last_tags[0] as usize].clone();
-                        vec![vec![res]]
+                    (true, false) => {
+                        // the history is empty when no sentence is longer than one word, paths
+                        // of a single word are their last tags
+                        let srl_history: Option<OrtOwnedTensor<i64, _>> =
+                            match srl_history.is_valid() {
+                                true => Some(srl_history.try_extract()?),
+                                false => None,
+                            };
+                        let srl_last_tags: OrtOwnedTensor<i64, _> = srl_last_tags.try_extract()?;
+                        let rows = Self::pipeline_batch_srl(
+                            srl_history.as_ref().map_or(&[], |x| x.as_slice().unwrap()),
+                            srl_last_tags.as_slice().unwrap_or(&[]),
+                            vocab,
+                            &model_word_nums,
+                        );
+                        let rows = Self::project(rows, alignments, |x, rows| {
+                            let empty = vec![String::from("O"); x.len()];
+                            Self::project_rows(x, rows, empty, |tags| x.chunks(tags, bioes))
+                        });
+                        Some(
+                            rows.iter()
+                                .map(|rows| SrlFrame::from_rows(rows))
+                                .collect_vec(),
+                        )
                     }
+                    (false, _) => None,
                 }
-            }),
+            }
             None => None,
         };
 
-        let srl = srl.map(|mut all_srl| {
-            let mut results = Vec::new();
-            for stn_len in &word_nums {
-                let mut result = Vec::new();
-                for _i in 0..*stn_len {
-                    result.push(all_srl.remove(0))
-                }
-                results.push(result)
-            }
-            results
-        });
-
         let cls_word_num = word_nums.iter().map(|&x| x + 1).collect_vec();
-        let &max_cls_stn_length = cls_word_num.iter().max().unwrap();
 
+        let mut dep_confidence = None;
+        let mut dep_kbest = None;
         let dep = match self.vocabs.dep.as_ref() {
-            Some(vocab) => Some({
-                let dep_head: OrtOwnedTensor<f32, _> = result.remove(0).try_extract()?;
-                let dep_labels: OrtOwnedTensor<i64, _> = result.remove(0).try_extract()?;
-                let dep_head_decoded =
-                    eisner(dep_head.as_slice().unwrap(), cls_word_num.as_slice(), true);
-                dep_head_decoded
-                    .iter()
-                    .enumerate()
-                    .map(|(batch, stn_head)| {
-                        stn_head
-                            .iter()
-                            .enumerate()
-                            .map(|(idx, &head)| DEP {
-                                arc: head,
-                                rel: vocab[*(dep_labels
-                                    .borrow()
-                                    .get([batch, idx + 1, head])
-                                    .unwrap()) as usize]
-                                    .clone(),
-                            })
-                            .collect_vec()
-                    })
-                    .collect_vec()
-            }),
+            Some(vocab) => {
+                let dep_head = result.remove(0);
+                let dep_labels = result.remove(0);
+                match tasks.contains(Tasks::DEP) {
+                    true => {
+                        let dep_head: OrtOwnedTensor<f32, _> = dep_head.try_extract()?;
+                        let (mut dep_label_ids, mut label_probs) =
+                            extract_ids!(dep_labels, self.logits.dep_labels);
+                        // arcs between the words are taken from arcs between their model words
+                        let pairs = alignments.map(|alignments| {
+                            ArcPairs::new(&dep_head.view().into_dyn(), alignments)
+                        });
+                        let pooled_head = pairs
+                            .as_ref()
+                            .map(|pairs| pairs.gather(&dep_head.view().into_dyn()));
+                        if let Some(pairs) = pairs.as_ref() {
+                            dep_label_ids = pairs.gather(&dep_label_ids.view());
+                            label_probs = label_probs.map(|probs| pairs.gather(&probs.view()));
+                        }
+                        let head_scores = match pooled_head.as_ref() {
+                            Some(pooled_head) => pooled_head.as_slice().unwrap(),
+                            None => dep_head.as_slice().unwrap(),
+                        };
+                        let joint_head = match (self.joint_labels, label_probs.as_ref()) {
+                            (true, Some(probs)) => Some(Self::joint_arc_scores(head_scores, probs)),
+                            _ => None,
+                        };
+                        let dep = Self::pipeline_batch_dep(
+                            joint_head.as_deref().unwrap_or(head_scores),
+                            &dep_label_ids.view(),
+                            vocab,
+                            &cls_word_num,
+                            self.tree_decoder,
+                            self.root_policy,
+                        );
+                        if self.confidence {
+                            dep_confidence = Some(Self::pipeline_batch_dep_confidence(
+                                head_scores,
+                                label_probs.as_ref(),
+                                &dep,
+                                &cls_word_num,
+                            ));
+                        }
+                        if self.kbest > 1 && self.logits.dep_labels {
+                            let label_logits: OrtOwnedTensor<f32, _> = dep_labels.try_extract()?;
+                            let pooled_logits = pairs
+                                .as_ref()
+                                .map(|pairs| pairs.gather(&label_logits.view().into_dyn()));
+                            let arcs = dep
+                                .iter()
+                                .map(|stn_dep| {
+                                    stn_dep
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(idx, x)| (idx + 1, x.arc))
+                                        .collect_vec()
+                                })
+                                .collect_vec();
+                            dep_kbest = Some(Self::pipeline_batch_labels_kbest(
+                                &pooled_logits
+                                    .as_ref()
+                                    .map_or_else(|| label_logits.view().into_dyn(), |x| x.view()),
+                                vocab,
+                                &arcs,
+                                self.kbest,
+                            ));
+                        }
+                        Some(dep)
+                    }
+                    false => None,
+                }
+            }
             None => None,
         };
 
-        let block_size = max_cls_stn_length * max_cls_stn_length;
+        let mut sdp_confidence = None;
+        let mut sdp_kbest = None;
         let sdp = match self.vocabs.sdp.as_ref() {
-            Some(vocab) => Some({
-                let sdp_head: OrtOwnedTensor<f32, _> = result.remove(0).try_extract()?;
-                let sdp_labels: OrtOwnedTensor<i64, _> = result.remove(0).try_extract()?;
-
-                let sdp_head_decoded =
-                    eisner(sdp_head.as_slice().unwrap(), cls_word_num.as_slice(), false);
-                sdp_head
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, &score)| {
-                        let batch = idx / block_size;
-                        let block_idx = idx % block_size; // Z 字形
-
-                        let current = block_idx / max_cls_stn_length;
-                        let target = block_idx % max_cls_stn_length;
-                        if current < 1 || current > word_nums[batch] || target > word_nums[batch] {
-                            // 这里要考虑虚节点
-                            None
-                        } else {
-                            if score > 0f32 || sdp_head_decoded[batch][current] == target {
-                                let label = vocab[*(sdp_labels
-                                    .borrow()
-                                    .get([batch, current, target])
-                                    .unwrap())
-                                    as usize]
-                                    .clone();
-                                return Some((batch, current, target, label));
-                            }
-                            None
+            Some(vocab) => {
+                let sdp_head = result.remove(0);
+                let sdp_labels = result.remove(0);
+                match tasks.contains(Tasks::SDP) {
+                    true => {
+                        let sdp_head: OrtOwnedTensor<f32, _> = sdp_head.try_extract()?;
+                        let (mut sdp_label_ids, mut label_probs) =
+                            extract_ids!(sdp_labels, self.logits.sdp_labels);
+                        // edges between the words are taken from edges between their model words
+                        let pairs = alignments.map(|alignments| {
+                            ArcPairs::new(&sdp_head.view().into_dyn(), alignments)
+                        });
+                        let pooled_head = pairs
+                            .as_ref()
+                            .map(|pairs| pairs.gather(&sdp_head.view().into_dyn()));
+                        if let Some(pairs) = pairs.as_ref() {
+                            sdp_label_ids = pairs.gather(&sdp_label_ids.view());
+                            label_probs = label_probs.map(|probs| pairs.gather(&probs.view()));
                         }
-                    })
-                    .filter(|x| x.is_some())
-                    .map(|x| x.unwrap())
-                    .collect_vec()
-            }),
+                        let head_scores = match pooled_head.as_ref() {
+                            Some(pooled_head) => pooled_head.as_slice().unwrap(),
+                            None => sdp_head.as_slice().unwrap(),
+                        };
+                        let joint_head = match (self.joint_labels, label_probs.as_ref()) {
+                            (true, Some(probs)) => Some(Self::joint_arc_scores(head_scores, probs)),
+                            _ => None,
+                        };
+                        let sdp = self.pipeline_batch_sdp(
+                            head_scores,
+                            joint_head.as_deref(),
+                            &sdp_label_ids.view(),
+                            vocab,
+                            &cls_word_num,
+                        );
+                        if self.confidence {
+                            sdp_confidence = Some(Self::pipeline_batch_sdp_confidence(
+                                head_scores,
+                                label_probs.as_ref(),
+                                &sdp,
+                                &cls_word_num,
+                            ));
+                        }
+                        if self.kbest > 1 && self.logits.sdp_labels {
+                            let label_logits: OrtOwnedTensor<f32, _> = sdp_labels.try_extract()?;
+                            let pooled_logits = pairs
+                                .as_ref()
+                                .map(|pairs| pairs.gather(&label_logits.view().into_dyn()));
+                            let arcs = sdp
+                                .iter()
+                                .map(|edges| edges.iter().map(|x| (x.src, x.tgt)).collect_vec())
+                                .collect_vec();
+                            sdp_kbest = Some(Self::pipeline_batch_labels_kbest(
+                                &pooled_logits
+                                    .as_ref()
+                                    .map_or_else(|| label_logits.view().into_dyn(), |x| x.view()),
+                                vocab,
+                                &arcs,
+                                self.kbest,
+                            ));
+                        }
+                        Some(sdp)
+                    }
+                    false => None,
+                }
+            }
             None => None,
         };
 
-        let sdp = sdp.map(|all_sdp| {
-            let mut results = vec![Vec::new(); batch_size];
+        let confidence = match self.confidence {
+            true => {
+                let mut confidence = vec![Confidence::default(); batch_size];
+                fill_results!(confidence, seg, seg_confidence);
+                fill_results!(confidence, pos, pos_confidence);
+                fill_results!(confidence, ner, ner_confidence);
+                fill_results!(confidence, srl, srl_confidence);
+                fill_results!(confidence, dep, dep_confidence);
+                fill_results!(confidence, sdp, sdp_confidence);
+                Some(confidence)
+            }
+            false => None,
+        };
 
-            for (batch, current, target, tag) in all_sdp {
-                results[batch].push(SDP {
-                    src: current,
-                    tgt: target,
-                    rel: tag,
-                });
+        let kbest = match self.kbest > 1 {
+            true => {
+                let mut kbest = vec![KBest::default(); batch_size];
+                fill_results!(kbest, seg, seg_kbest);
+                fill_results!(kbest, srl, srl_kbest);
+                fill_results!(kbest, dep, dep_kbest);
+                fill_results!(kbest, sdp, sdp_kbest);
+                Some(kbest)
             }
-            results
-        });
+            false => None,
+        };
 
-        let texts = option_vec_to_vec_option!(texts, batch_size);
-        let pos = option_vec_to_vec_option!(pos, batch_size);
-        let ner = option_vec_to_vec_option!(ner, batch_size);
-        let srl = option_vec_to_vec_option!(srl, batch_size);
-        let dep = option_vec_to_vec_option!(dep, batch_size);
-        let sdp = option_vec_to_vec_option!(sdp, batch_size);
+        let mut results = vec![LTPResult::default(); batch_size];
+        fill_results!(results, seg, texts);
+        fill_results!(results, char_offsets, char_offsets);
+        fill_results!(results, byte_offsets, byte_offsets);
+        fill_results!(results, pos, pos);
+        fill_results!(results, ner, ner);
+        fill_results!(results, entities, entities);
+        fill_results!(results, srl, srl);
+        fill_results!(results, dep, dep);
+        fill_results!(results, sdp, sdp);
+        fill_results!(results, confidence, confidence);
+        fill_results!(results, kbest, kbest);
+        Ok(results.into_iter().zip(word_nums).collect_vec())
+    }
 
-        let results = multizip((texts, pos, ner, srl, dep, sdp))
-            .into_iter()
-            .map(
-                |(one_seg, one_pos, one_ner, one_srl, one_dep, one_sdp)| LTPResult {
-                    seg: one_seg,
-                    pos: one_pos,
-                    ner: one_ner,
-                    dep: one_dep,
-                    sdp: one_sdp,
-                    srl: one_srl,
-                },
-            )
+    /// Run the pipeline over sentences grouped by length, so that short sentences are not
+    /// padded to the longest one of the input. Results are returned in input order.
+    pub fn pipeline_batch_bucketed(
+        &self,
+        sentences: &[String],
+        tasks: Tasks,
+        policy: BatchPolicy,
+    ) -> Result<Vec<LTPResult>> {
+        // chars are never fewer than tokens, plus `[CLS]` and `[SEP]`
+        let lengths = sentences
+            .iter()
+            .map(|s| s.chars().count() + 2)
             .collect_vec();
-        Ok(results)
+
+        let mut results = vec![None; sentences.len()];
+        for bucket in batch::buckets(&lengths, policy) {
+            let batch = bucket
+                .iter()
+                .map(|&idx| sentences[idx].clone())
+                .collect_vec();
+            let batch_results = self.pipeline_batch_with(&batch, tasks)?;
+            for (idx, result) in bucket.into_iter().zip(batch_results) {
+                results[idx] = Some(result);
+            }
+        }
+        // the buckets hold every sentence exactly once
+        Ok(results.into_iter().map(|x| x.unwrap()).collect_vec())
+    }
+
+    /// Analyze a stream of sentences lazily, `batch_size` sentences at a time, see
+    /// [`AnalyzeIter`] for the options.
+    pub fn analyze_iter<I>(&self, inputs: I, batch_size: usize) -> AnalyzeIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        AnalyzeIter::new(self, inputs.into_iter(), batch_size)
     }
 
-    pub fn pipeline(&mut self, sentence: &String) -> Result<LTPResult> {
+    pub fn pipeline(&self, sentence: &String) -> Result<LTPResult> {
+        self.pipeline_with(sentence, Tasks::all())
+    }
+
+    pub fn pipeline_with(&self, sentence: &String, tasks: Tasks) -> Result<LTPResult> {
         let fake_batch = vec![sentence.clone()];
-        let results = self.pipeline_batch(&fake_batch);
+        let results = self.pipeline_batch_with(&fake_batch, tasks);
+        results.map(|mut result| result.remove(0))
+    }
+
+    pub fn pipeline_words(&self, words: &[String], tasks: Tasks) -> Result<LTPResult> {
+        let fake_batch = vec![words.to_vec()];
+        let results = self.pipeline_batch_words(&fake_batch, tasks);
         results.map(|mut result| result.remove(0))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{LTPError, LTP};
+    use crate::align::Alignment;
+    use crate::dictionary::{Dictionary, MatchStrategy};
+    use crate::interface::Logits;
+    use crate::{
+        Entity, KBest, LTPError, LTPResult, Signature, SrlArg, SrlFrame, Tasks, TensorInfo, Vocab,
+        LTP,
+    };
+    use itertools::Itertools;
+    use onnxruntime::ndarray::{ArrayD, IxDyn};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        assert_send_sync::<LTP>();
+    }
+
+    #[test]
+    fn test_stitch() {
+        // two windows of 3 and 2 words decoded for SRL only, frames do not tell the words
+        let window = |predicate: usize, arg: usize, word_num: usize| {
+            let frame = SrlFrame {
+                predicate,
+                arguments: vec![SrlArg {
+                    role: "A0".into(),
+                    start: arg,
+                    end: arg,
+                }],
+            };
+            let rows = (0..word_num)
+                .map(|_| vec![(vec![String::from("O"); word_num], 0.0)])
+                .collect();
+            let result = LTPResult {
+                srl: Some(vec![frame]),
+                kbest: Some(KBest {
+                    srl: Some(rows),
+                    ..KBest::default()
+                }),
+                ..LTPResult::default()
+            };
+            (result, word_num)
+        };
+        let results = vec![window(1, 0, 3), window(0, 1, 2)];
+        let stitched = LTP::stitch(results, &[vec![(0, 0), (4, 12)]]);
+        let srl = stitched[0].srl.as_ref().unwrap();
+        assert_eq!((srl[0].predicate, srl[0].arguments[0].start), (1, 0));
+        assert_eq!((srl[1].predicate, srl[1].arguments[0].start), (3, 4));
+        let rows = stitched[0].kbest.as_ref().unwrap().srl.as_ref().unwrap();
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|paths| paths[0].0.len() == 5));
+    }
+
+    #[test]
+    fn test_word_spans() {
+        // "iPhone12价格" given as words, read by the model as "iPhone 12", "价", "格"
+        let words = vec![
+            String::from("iPhone"),
+            String::from("12"),
+            String::from("价格"),
+        ];
+        let joined = words.join(" ");
+        let chars = joined.chars().collect_vec();
+        let spans = LTP::word_spans(&words, 1);
+        for (word, &(start, end)) in words.iter().zip(&spans) {
+            assert_eq!(&chars[start..end].iter().collect::<String>(), word);
+        }
+        let alignment = Alignment::new(&spans, &[(0, 9), (10, 11), (11, 12)]);
+        assert_eq!(alignment.len(), words.len());
+        assert_eq!(alignment.tags(&["nz", "n", "n"]), vec!["nz", "nz", "n"]);
+    }
+
+    #[test]
+    fn test_concat_offsets() {
+        // "iPhone12价格" given as words, tokenized as "iPhone 12 价格"
+        let words = vec![
+            String::from("iPhone"),
+            String::from("12"),
+            String::from("价格"),
+        ];
+        assert_eq!(LTP::word_spans(&words, 1), vec![(0, 6), (7, 9), (10, 12)]);
+        let mut result = LTPResult {
+            char_offsets: Some(LTP::word_spans(&words, 1)),
+            byte_offsets: Some(vec![(0, 6), (7, 9), (10, 16)]),
+            entities: Some(vec![Entity {
+                label: "Nz".into(),
+                start_word: 0,
+                end_word: 1,
+                text: "iPhone 12".into(),
+                char_start: 0,
+                char_end: 9,
+            }]),
+            ..LTPResult::default()
+        };
+        LTP::concat_offsets(&mut result, &words);
+        assert_eq!(result.char_offsets, Some(vec![(0, 6), (6, 8), (8, 10)]));
+        assert_eq!(result.byte_offsets, Some(vec![(0, 6), (6, 8), (8, 14)]));
+        let entity = &result.entities.as_ref().unwrap()[0];
+        assert_eq!(
+            (entity.text.as_str(), entity.char_start, entity.char_end),
+            ("iPhone12", 0, 8)
+        );
+    }
+
+    #[test]
+    fn test_logits() {
+        let output = |name: &str, element_type: &str, rank: usize| TensorInfo {
+            name: name.to_string(),
+            element_type: element_type.to_string(),
+            dimensions: vec![None; rank],
+        };
+        let labels = Some(vec![String::from("O")]);
+        let vocabs = Vocab {
+            seg: labels.clone(),
+            pos: labels.clone(),
+            ner: None,
+            srl: None,
+            dep: labels,
+            sdp: None,
+        };
+        let mut signature = Signature {
+            inputs: vec![],
+            outputs: vec![
+                output("seg_output", "Int64", 2),
+                output("pos_logits", "Float", 3),
+                output("dep_arc", "Float", 3),
+                output("dep_label_logits", "Float", 4),
+            ],
+        };
+        let logits = Logits::of(&signature, &vocabs).unwrap();
+        assert!(!logits.seg && logits.pos && logits.dep_labels);
+
+        // logits read in place of another head, or of the wrong rank, are refused
+        signature.outputs.swap(0, 1);
+        assert!(Logits::of(&signature, &vocabs).is_err());
+        signature.outputs.swap(0, 1);
+        signature.outputs[1] = output("pos_logits", "Float", 2);
+        assert!(Logits::of(&signature, &vocabs).is_err());
+        signature.outputs.pop();
+        assert!(Logits::of(&signature, &vocabs).is_err());
+    }
+
+    #[test]
+    fn test_constrain() {
+        // every char is a word for the model, "汤姆去" is in the dictionary
+        let sentence = String::from("他叫汤姆去");
+        let offsets = [(0, 0), (0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (0, 0)];
+        let vocab = ["B", "I", "E", "S"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect_vec();
+        let emissions = ArrayD::from_shape_fn(IxDyn(&[1, 5, 4]), |idx| match idx[2] {
+            3 => 0.0,
+            _ => -1.0,
+        });
+        let mut dictionary = Dictionary::new(MatchStrategy::Longest);
+        dictionary.add_word("汤姆去", 1.0);
+        let words = LTP::pipeline_batch_constrain(
+            &dictionary,
+            &emissions.view(),
+            &vocab,
+            &[sentence],
+            &[&offsets],
+            &[5],
+        );
+        assert_eq!(words, vec![vec![(0, 1), (1, 2), (2, 5)]]);
+    }
 
     #[test]
     fn test_interface() -> Result<(), LTPError> {
@@ -478,12 +2379,84 @@ mod tests {
         let j = serde_json::to_string(&result).unwrap();
         println!("{}", j);
 
+        let seg = result.seg.as_ref().unwrap();
+        for (idx, &(start, end)) in result.byte_offsets.as_ref().unwrap().iter().enumerate() {
+            assert_eq!(&sentence[start..end], seg[idx]);
+        }
+
         let sentence2 = String::from("我爱赛尔!");
         let sentences = vec![sentence, sentence2];
         let result = ltp.pipeline_batch(&sentences)?;
 
         let j = serde_json::to_string(&result).unwrap();
         println!("{}", j);
+
+        for one in &result {
+            let word_num = one.seg.as_ref().unwrap().len();
+            for frame in one.srl.as_ref().unwrap() {
+                assert!(frame.predicate < word_num && !frame.arguments.is_empty());
+                assert!(frame
+                    .arguments
+                    .iter()
+                    .all(|x| x.start <= x.end && x.end < word_num));
+            }
+            let seg = one.seg.as_ref().unwrap();
+            for entity in one.entities.as_ref().unwrap() {
+                assert_eq!(
+                    entity.text,
+                    seg[entity.start_word..=entity.end_word].concat()
+                );
+            }
+        }
+
+        let results = ltp
+            .analyze_iter(sentences.clone().into_iter().cycle().take(5), 2)
+            .collect::<Result<Vec<_>, LTPError>>()?;
+        assert_eq!(results.len(), 5);
+
+        let result = ltp.pipeline_batch_with(&sentences, Tasks::SEG | Tasks::POS)?;
+        for one in &result {
+            assert_eq!(
+                one.seg.as_ref().unwrap().len(),
+                one.pos.as_ref().unwrap().len()
+            );
+            assert!(one.ner.is_none() && one.srl.is_none());
+            assert!(one.dep.is_none() && one.sdp.is_none());
+        }
+
+        ltp.set_confidence(true)?;
+        let result = ltp.pipeline_batch_with(&sentences, Tasks::SEG | Tasks::DEP)?;
+        for one in &result {
+            let dep = one.confidence.as_ref().unwrap().dep.as_ref().unwrap();
+            assert_eq!(dep.len(), one.dep.as_ref().unwrap().len());
+            assert!(dep.iter().all(|&x| (0.0..=1.0).contains(&x)));
+        }
+
+        let words = ["iPhone", "12", "价格", "是", "多少"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect_vec();
+        let result = ltp.pipeline_words(&words, Tasks::all())?;
+        assert_eq!(result.seg.as_ref(), Some(&words));
+        assert_eq!(result.pos.as_ref().unwrap().len(), words.len());
+        assert_eq!(result.ner.as_ref().unwrap().len(), words.len());
+        assert_eq!(result.dep.as_ref().unwrap().len(), words.len());
+        for frame in result.srl.as_ref().unwrap() {
+            assert!(frame.predicate < words.len());
+            assert!(frame.arguments.iter().all(|x| x.end < words.len()));
+        }
+        for edge in result.sdp.as_ref().unwrap() {
+            assert!(edge.src <= words.len() && edge.tgt <= words.len());
+        }
+
+        let mut dictionary = Dictionary::new(MatchStrategy::Longest);
+        dictionary.add_word("汤姆去", 1.0);
+        ltp.set_dictionary(Some(dictionary));
+        let result = ltp.pipeline(&sentences[0])?;
+        let seg = result.seg.as_ref().unwrap();
+        assert!(seg.contains(&String::from("汤姆去")));
+        assert_eq!(result.pos.as_ref().unwrap().len(), seg.len());
+        assert_eq!(result.dep.as_ref().unwrap().len(), seg.len());
         Ok(())
     }
 }
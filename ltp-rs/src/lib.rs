@@ -2,15 +2,37 @@ pub mod preinclude;
 
 mod error;
 
+mod batch;
+pub mod dictionary;
 pub mod eisner;
 pub mod entities;
+pub mod formats;
+pub mod mst;
+pub mod sdp;
+pub mod sentence;
 pub mod viterbi;
 
+mod align;
+mod pool;
+mod scores;
+mod stream;
 mod tokenizer;
 mod vocabs;
+mod window;
 
 mod interface;
 
+#[cfg(feature = "service")]
+pub mod service;
+
+pub use batch::BatchPolicy;
+pub use eisner::RootPolicy;
 pub use error::LTPError;
 pub use error::Result;
-pub use interface::{LTPResult, DEP, LTP, SDP};
+pub use interface::{
+    Confidence, Entity, KBest, LTPResult, Scored, Signature, SrlArg, SrlFrame, Tasks, TensorInfo,
+    TreeDecoder, DEP, LTP, SDP,
+};
+pub use sdp::SdpPolicy;
+pub use stream::AnalyzeIter;
+pub use vocabs::Vocab;
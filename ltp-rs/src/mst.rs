@@ -0,0 +1,215 @@
+use num_traits::Float;
+
+use crate::eisner::RootPolicy;
+
+/// first cycle found following the heads, the root has no head so it is never in a cycle
+fn find_cycle(heads: &[usize]) -> Option<Vec<usize>> {
+    let mut visited = vec![0; heads.len()];
+    for start in 1..heads.len() {
+        let mut node = start;
+        while node != 0 && visited[node] == 0 {
+            visited[node] = start;
+            node = heads[node];
+        }
+        if node != 0 && visited[node] == start {
+            let mut cycle = vec![node];
+            let mut next = heads[node];
+            while next != node {
+                cycle.push(next);
+                next = heads[next];
+            }
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+fn best_by_score<I: Iterator<Item = (usize, f64)>>(candidates: I) -> (usize, f64) {
+    candidates.fold((0, f64::NEG_INFINITY), |best, x| match x.1 > best.1 {
+        true => x,
+        false => best,
+    })
+}
+
+/// Chu-Liu/Edmonds on `scores[dep][head]`, returns the head of every node, `0` for the root
+fn chu_liu_edmonds(scores: &[Vec<f64>]) -> Vec<usize> {
+    let n = scores.len();
+    let mut heads = vec![0; n];
+    for dep in 1..n {
+        heads[dep] = best_by_score((0..n).filter(|&h| h != dep).map(|h| (h, scores[dep][h]))).0;
+    }
+    let cycle = match find_cycle(&heads) {
+        Some(cycle) => cycle,
+        None => return heads,
+    };
+
+    // contract the cycle into one node, placed after the nodes outside of it
+    let mut in_cycle = vec![false; n];
+    cycle.iter().for_each(|&node| in_cycle[node] = true);
+    let outside = (0..n).filter(|&node| !in_cycle[node]).collect::<Vec<_>>();
+    let contracted = outside.len();
+
+    let mut sub_scores = vec![vec![f64::NEG_INFINITY; contracted + 1]; contracted + 1];
+    // head in the cycle of the arcs leaving it, and the cycle node of the arcs entering it
+    let mut leave = vec![0; contracted];
+    let mut enter = vec![0; contracted];
+    for (i, &dep) in outside.iter().enumerate() {
+        for (j, &head) in outside.iter().enumerate() {
+            sub_scores[i][j] = scores[dep][head];
+        }
+        let (head, score) = best_by_score(cycle.iter().map(|&head| (head, scores[dep][head])));
+        sub_scores[i][contracted] = score;
+        leave[i] = head;
+    }
+    for (j, &head) in outside.iter().enumerate() {
+        // entering the cycle at `dep` breaks the arc of the cycle to `dep`
+        let (dep, score) = best_by_score(
+            cycle
+                .iter()
+                .map(|&dep| (dep, scores[dep][head] - scores[dep][heads[dep]])),
+        );
+        sub_scores[contracted][j] = score;
+        enter[j] = dep;
+    }
+
+    let sub_heads = chu_liu_edmonds(&sub_scores);
+    for (i, &dep) in outside.iter().enumerate().skip(1) {
+        heads[dep] = match sub_heads[i] == contracted {
+            true => leave[i],
+            false => outside[sub_heads[i]],
+        };
+    }
+    let head = sub_heads[contracted];
+    heads[enter[head]] = outside[head];
+    heads
+}
+
+/// Non-projective decoding of dependency trees with exactly one word attached to the root, see
+/// [`mst_with_root`].
+pub fn mst<T>(scores: &[T], stn_length: &[usize], remove_root: bool) -> Vec<Vec<usize>>
+where
+    T: Float,
+{
+    mst_with_root(scores, stn_length, remove_root, RootPolicy::Single)
+}
+
+/// Non-projective decoding of dependency trees, the maximum spanning arborescence of every
+/// sentence is found by Chu-Liu/Edmonds.
+///
+/// Same layout as [`crate::eisner::eisner`]: `scores` is `[b, w, n]`, the score of the arc from
+/// head `n` to word `w`, where the root is word `0` and counted in `stn_length`.
+pub fn mst_with_root<T>(
+    scores: &[T],
+    stn_length: &[usize],
+    remove_root: bool,
+    root: RootPolicy,
+) -> Vec<Vec<usize>>
+where
+    T: Float,
+{
+    let max_stn_len = *stn_length.iter().max().unwrap();
+    let score_block_size = max_stn_len * max_stn_len;
+
+    let mut res = Vec::new();
+    for (b, &stn_len) in stn_length.iter().enumerate() {
+        let bscore_bias = b * score_block_size;
+        let mut sub_scores = (0..stn_len)
+            .map(|dep| {
+                (0..stn_len)
+                    .map(|head| match dep == 0 || dep == head {
+                        true => f64::NEG_INFINITY,
+                        false => scores[bscore_bias + dep * max_stn_len + head]
+                            .to_f64()
+                            .unwrap(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // a penalty larger than any difference of tree scores on every root attachment, the
+        // best tree then has a single root attachment and is the best of those
+        let finite = sub_scores.iter().flatten().filter(|x| x.is_finite());
+        let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &x| {
+            (min.min(x), max.max(x))
+        });
+        if root == RootPolicy::Single && min <= max {
+            let penalty = (max - min + 1.0) * stn_len as f64;
+            sub_scores.iter_mut().skip(1).for_each(|x| x[0] -= penalty);
+        }
+
+        let heads = match stn_len {
+            0 => Vec::new(),
+            _ => chu_liu_edmonds(&sub_scores),
+        };
+        res.push(heads.into_iter().skip(remove_root as usize).collect());
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eisner::RootPolicy;
+    use crate::mst::{mst, mst_with_root};
+
+    #[test]
+    fn test_mst() {
+        // root + 3 words, padded to 4, with a crossing best tree: 0 -> 2, 2 -> 1, 1 -> 3
+        let n = 4;
+        let mut scores = vec![-10.0f32; 2 * n * n];
+        let mut arc = |b: usize, head: usize, dep: usize, score: f32| {
+            scores[b * n * n + dep * n + head] = score
+        };
+        arc(0, 0, 2, 5.0);
+        arc(0, 0, 1, 4.0);
+        arc(0, 0, 3, 4.5);
+        arc(0, 2, 1, 3.0);
+        arc(0, 1, 3, 3.0);
+        arc(0, 1, 2, 3.5);
+        arc(0, 3, 1, 1.0);
+        // second sentence: root + 2 words in a cycle
+        arc(1, 1, 2, 2.0);
+        arc(1, 2, 1, 2.0);
+        arc(1, 0, 1, 1.0);
+
+        let output = mst(&scores, &[4, 3], true);
+        assert_eq!(output, vec![vec![2, 0, 1], vec![0, 1]]);
+
+        // brute force over all single rooted trees of the first sentence
+        let score = |heads: &[usize]| -> f32 {
+            heads
+                .iter()
+                .enumerate()
+                .map(|(idx, &head)| scores[(idx + 1) * n + head])
+                .sum()
+        };
+        let mut best = f32::NEG_INFINITY;
+        for h1 in 0..n {
+            for h2 in 0..n {
+                for h3 in 0..n {
+                    let heads = [h1, h2, h3];
+                    let roots = heads.iter().filter(|&&h| h == 0).count();
+                    let acyclic = (1..n).all(|mut node| {
+                        for _ in 0..n {
+                            if node == 0 {
+                                return true;
+                            }
+                            node = heads[node - 1];
+                        }
+                        false
+                    });
+                    if roots == 1 && acyclic && heads.iter().enumerate().all(|(i, &h)| h != i + 1) {
+                        best = best.max(score(&heads));
+                    }
+                }
+            }
+        }
+        assert_eq!(score(&output[0]), best);
+
+        // every word of the first sentence prefers the root
+        scores[3 * n] = 6.0;
+        let single = mst_with_root(&scores, &[4], true, RootPolicy::Single);
+        assert_eq!(single, vec![vec![2, 0, 1]]);
+        let multiple = mst_with_root(&scores, &[4], true, RootPolicy::Multiple);
+        assert_eq!(multiple, vec![vec![0, 0, 0]]);
+    }
+}
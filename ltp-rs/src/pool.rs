@@ -0,0 +1,92 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+
+use onnxruntime::session::Session;
+
+struct PooledSession(Session<'static>);
+
+// SAFETY: a `Session` only holds pointers to the ONNX Runtime session, allocator and memory
+// info, which ONNX Runtime does not tie to the thread that created them. The pool hands every
+// session to a single guard at a time, so a session is never used by two threads at once.
+unsafe impl Send for PooledSession {}
+
+/// Pool of sessions loaded from the same model, `Session::run` needs `&mut self` so every
+/// session serves one call at a time and concurrent calls are spread over the pool.
+///
+/// The sessions share the global environment but the wrapper has no way to share
+/// initializers between sessions, so each one holds its own copy of the weights, a pool of
+/// `n` sessions takes `n` times the memory of the model.
+pub(crate) struct SessionPool {
+    idle: Mutex<Vec<PooledSession>>,
+    released: Condvar,
+    size: usize,
+}
+
+pub(crate) struct SessionGuard<'a> {
+    pool: &'a SessionPool,
+    session: Option<PooledSession>,
+}
+
+impl SessionPool {
+    pub(crate) fn new(sessions: Vec<Session<'static>>) -> SessionPool {
+        assert!(!sessions.is_empty(), "session pool can not be empty");
+        SessionPool {
+            size: sessions.len(),
+            idle: Mutex::new(sessions.into_iter().map(PooledSession).collect()),
+            released: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Take an idle session, or wait until any session is released when they are all busy.
+    pub(crate) fn acquire(&self) -> SessionGuard<'_> {
+        let mut idle = self
+            .idle
+            .lock()
+            .expect("Failed to acquire lock: another thread panicked?");
+        loop {
+            if let Some(session) = idle.pop() {
+                return SessionGuard {
+                    pool: self,
+                    session: Some(session),
+                };
+            }
+            idle = self
+                .released
+                .wait(idle)
+                .expect("Failed to acquire lock: another thread panicked?");
+        }
+    }
+}
+
+impl Deref for SessionGuard<'_> {
+    type Target = Session<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.session.as_ref().unwrap().0
+    }
+}
+
+impl DerefMut for SessionGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.session.as_mut().unwrap().0
+    }
+}
+
+impl Drop for SessionGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(session) = self.session.take() {
+            // the session goes back even when a panicking call drops the guard
+            let mut idle = self
+                .pool
+                .idle
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            idle.push(session);
+            self.pool.released.notify_one();
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub use bitflags;
 pub use itertools;
 pub use onnxruntime;
 
@@ -10,3 +11,6 @@ pub use serde;
 pub use serde_json;
 pub use thiserror;
 pub use tokenizers;
+
+#[cfg(feature = "service")]
+pub use tokio;
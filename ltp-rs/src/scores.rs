@@ -0,0 +1,92 @@
+use onnxruntime::ndarray::{ArrayD, ArrayViewD, Axis};
+
+/// Argmax and softmax over the last axis of `logits`, returns the best label ids and their
+/// probabilities, both shaped as `logits` without its last axis.
+pub(crate) fn softmax_argmax(logits: &ArrayViewD<f32>) -> (ArrayD<i64>, ArrayD<f32>) {
+    let last = Axis(logits.ndim() - 1);
+    let ids = logits.map_axis(last, |row| {
+        row.iter()
+            .enumerate()
+            .fold((0, f32::NEG_INFINITY), |best, (idx, &x)| match x > best.1 {
+                true => (idx, x),
+                false => best,
+            })
+            .0 as i64
+    });
+    let probs = logits.map_axis(last, |row| {
+        let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        if max == f32::NEG_INFINITY {
+            return 0.0;
+        }
+        // the best label has exp(max - max) = 1
+        1.0 / row.iter().map(|&x| (x - max).exp()).sum::<f32>()
+    });
+    (ids, probs)
+}
+
+/// Log softmax over the last axis of `logits`.
+pub(crate) fn log_softmax(logits: &ArrayViewD<f32>) -> ArrayD<f32> {
+    let last = Axis(logits.ndim() - 1);
+    let mut log_probs = logits.to_owned();
+    for mut row in log_probs.lanes_mut(last) {
+        let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let log_sum = max + row.iter().map(|&x| (x - max).exp()).sum::<f32>().ln();
+        row.mapv_inplace(|x| x - log_sum);
+    }
+    log_probs
+}
+
+/// Softmax of `scores` taken at `idx`.
+pub(crate) fn softmax_at(scores: &[f32], idx: usize) -> f32 {
+    let max = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    if max == f32::NEG_INFINITY {
+        return 0.0;
+    }
+    (scores[idx] - max).exp() / scores.iter().map(|&x| (x - max).exp()).sum::<f32>()
+}
+
+/// The `k` largest entries of the softmax of `scores`, best first.
+pub(crate) fn softmax_top_k(scores: &[f32], k: usize) -> Vec<(usize, f32)> {
+    let max = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    if max == f32::NEG_INFINITY {
+        return Vec::new();
+    }
+    let sum = scores.iter().map(|&x| (x - max).exp()).sum::<f32>();
+    let mut probs = scores
+        .iter()
+        .map(|&x| (x - max).exp() / sum)
+        .enumerate()
+        .collect::<Vec<_>>();
+    probs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    probs.truncate(k);
+    probs
+}
+
+pub(crate) fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use onnxruntime::ndarray::{arr2, arr3};
+
+    use crate::scores::{log_softmax, sigmoid, softmax_argmax, softmax_at, softmax_top_k};
+
+    #[test]
+    fn test_scores() {
+        let logits = arr3(&[[[0.0f32, 2.0_f32.ln()], [3.0_f32.ln(), 0.0]]]).into_dyn();
+        let (ids, probs) = softmax_argmax(&logits.view());
+        assert_eq!(ids, arr2(&[[1i64, 0]]).into_dyn());
+        assert!((probs[[0, 0]] - 2.0 / 3.0).abs() < 1e-6);
+        assert!((probs[[0, 1]] - 0.75).abs() < 1e-6);
+        let log_probs = log_softmax(&logits.view());
+        assert!((log_probs[[0, 0, 1]] - (2.0f32 / 3.0).ln()).abs() < 1e-6);
+
+        let scores = [0.0f32, 0.0, 2.0_f32.ln()];
+        assert!((softmax_at(&scores, 2) - 0.5).abs() < 1e-6);
+        let top = softmax_top_k(&scores, 2);
+        assert_eq!(top.iter().map(|x| x.0).collect::<Vec<_>>(), vec![2, 0]);
+        assert!((top[1].1 - 0.25).abs() < 1e-6);
+        assert!((sigmoid(0.0) - 0.5).abs() < 1e-6);
+    }
+}
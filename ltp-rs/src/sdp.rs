@@ -0,0 +1,292 @@
+use itertools::Itertools;
+
+/// How semantic dependency graphs are decoded from edge scores, the default keeps every edge
+/// scoring above `0` together with the backbone tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdpPolicy {
+    /// edges scoring above it are kept
+    pub threshold: f32,
+    /// most heads kept per word, the best scoring ones, `None` for no limit
+    pub max_parents: Option<usize>,
+    /// always keep the edges of the tree decoded from the same scores, which connects every
+    /// word to the root, off keeps the edges above the threshold only
+    pub backbone: bool,
+    /// attach the words the root does not reach by their best edge from the words it reaches,
+    /// even beyond `max_parents`
+    pub connect: bool,
+    /// drop the lowest scoring edge off the backbone of every cycle
+    pub remove_cycles: bool,
+}
+
+impl Default for SdpPolicy {
+    fn default() -> Self {
+        SdpPolicy {
+            threshold: 0.0,
+            max_parents: None,
+            backbone: true,
+            connect: false,
+            remove_cycles: false,
+        }
+    }
+}
+
+/// first cycle found by a depth first search over the heads of every word, as its edges
+fn find_cycle(heads: &[Vec<usize>]) -> Option<Vec<(usize, usize)>> {
+    // 0: unvisited, 1: on the stack, 2: done
+    let mut state = vec![0u8; heads.len()];
+    let mut stack = Vec::new();
+    for start in 0..heads.len() {
+        if state[start] != 0 {
+            continue;
+        }
+        state[start] = 1;
+        stack.push((start, 0));
+        while let Some(&mut (word, ref mut next)) = stack.last_mut() {
+            match heads[word].get(*next) {
+                Some(&head) => {
+                    *next += 1;
+                    match state[head] {
+                        0 => {
+                            state[head] = 1;
+                            stack.push((head, 0));
+                        }
+                        1 => {
+                            // the stack from `head` to `word` follows heads, closed by `word -> head`
+                            let from = stack.iter().position(|&(x, _)| x == head).unwrap();
+                            let path = stack[from..].iter().map(|&(x, _)| x).collect::<Vec<_>>();
+                            let mut cycle =
+                                path.windows(2).map(|x| (x[0], x[1])).collect::<Vec<_>>();
+                            cycle.push((word, head));
+                            return Some(cycle);
+                        }
+                        _ => {}
+                    }
+                }
+                None => {
+                    state[word] = 2;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    None
+}
+
+/// words reached from `from` following the edges from heads to words, `from` included
+fn reached(heads: &[Vec<usize>], from: usize) -> Vec<bool> {
+    let mut reached = vec![false; heads.len()];
+    reached[from] = true;
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for word in 0..heads.len() {
+            if !reached[word] && heads[word].iter().any(|&head| reached[head]) {
+                reached[word] = true;
+                changed = true;
+            }
+        }
+    }
+    reached
+}
+
+/// Decode the semantic graph of every sentence as `(word, head)` edges, sorted by word then
+/// head.
+///
+/// Same layout as [`crate::eisner::eisner`]: `scores` is `[b, w, n]`, the score of the edge from
+/// head `n` to word `w`, where the root is word `0` and counted in `stn_length`. `backbone` holds
+/// the heads of every word, root included, of the trees decoded from the same scores and is
+/// only used when [`SdpPolicy::backbone`] is on. A word scored as its own head keeps the
+/// edge like any other, [`SdpPolicy::remove_cycles`] drops it.
+///
+/// `ranks`, in the layout of `scores`, orders the heads of a word for
+/// [`SdpPolicy::max_parents`], [`SdpPolicy::remove_cycles`] and [`SdpPolicy::connect`] in place
+/// of the scores, the threshold always applies to the scores.
+pub fn decode(
+    scores: &[f32],
+    ranks: Option<&[f32]>,
+    stn_length: &[usize],
+    backbone: Option<&[Vec<usize>]>,
+    policy: &SdpPolicy,
+) -> Vec<Vec<(usize, usize)>> {
+    let max_stn_len = *stn_length.iter().max().unwrap();
+    let score_block_size = max_stn_len * max_stn_len;
+    let backbone = backbone.filter(|_| policy.backbone);
+
+    let mut res = Vec::new();
+    for (b, &stn_len) in stn_length.iter().enumerate() {
+        let bscore_bias = b * score_block_size;
+        let score = |word: usize, head: usize| scores[bscore_bias + word * max_stn_len + head];
+        let rank = |word: usize, head: usize| {
+            ranks.unwrap_or(scores)[bscore_bias + word * max_stn_len + head]
+        };
+        let on_backbone = |word: usize, head: usize| backbone.is_some_and(|x| x[b][word] == head);
+
+        // heads of every word, best first
+        let mut heads = vec![Vec::new(); stn_len];
+        for (word, word_heads) in heads.iter_mut().enumerate().skip(1) {
+            let mut candidates = (0..stn_len)
+                .filter(|&head| score(word, head) > policy.threshold || on_backbone(word, head))
+                .collect::<Vec<_>>();
+            candidates.sort_by(|&x, &y| {
+                let key = |head| (on_backbone(word, head), rank(word, head));
+                key(y)
+                    .partial_cmp(&key(x))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            if let Some(max_parents) = policy.max_parents {
+                candidates.truncate(max_parents.max(1));
+            }
+            *word_heads = candidates;
+        }
+
+        if policy.remove_cycles {
+            while let Some(cycle) = find_cycle(&heads) {
+                let weakest = cycle
+                    .iter()
+                    .filter(|&&(word, head)| !on_backbone(word, head))
+                    .min_by(|&&(w1, h1), &&(w2, h2)| {
+                        rank(w1, h1)
+                            .partial_cmp(&rank(w2, h2))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                let &(word, head) = weakest.unwrap_or(&cycle[0]);
+                heads[word].retain(|&x| x != head);
+            }
+        }
+
+        if policy.connect {
+            let mut from_root = reached(&heads, 0);
+            while let Some(unreached) = from_root.iter().position(|&x| !x) {
+                let mut best = None;
+                for word in (1..stn_len).filter(|&word| !from_root[word]) {
+                    // heads below the word would close a cycle
+                    let below = reached(&heads, word);
+                    for head in (0..stn_len).filter(|&head| from_root[head] && !below[head]) {
+                        let better = match best {
+                            Some((_, _, best)) => rank(word, head) > best,
+                            None => true,
+                        };
+                        if better {
+                            best = Some((word, head, rank(word, head)));
+                        }
+                    }
+                }
+                // the root is below no word, it only goes unpicked on NaN scores
+                let (word, head, _) = best.unwrap_or((unreached, 0, 0.0));
+                heads[word].push(head);
+                from_root = reached(&heads, 0);
+            }
+        }
+
+        let edges = heads
+            .iter()
+            .enumerate()
+            .flat_map(|(word, word_heads)| {
+                word_heads.iter().sorted().map(move |&head| (word, head))
+            })
+            .collect();
+        res.push(edges);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sdp::{decode, SdpPolicy};
+
+    #[test]
+    fn test_sdp() {
+        // root + 3 words with the backbone 0 -> 1 -> 2 -> 3, and root + 2 words padded to 4
+        let n = 4;
+        let mut scores = vec![-1.0f32; 2 * n * n];
+        let mut edge = |b: usize, head: usize, word: usize, score: f32| {
+            scores[b * n * n + word * n + head] = score
+        };
+        edge(0, 0, 1, 2.0);
+        edge(0, 1, 2, 1.0);
+        edge(0, 2, 3, 1.0);
+        edge(0, 3, 1, 0.5);
+        edge(0, 1, 3, 0.8);
+        edge(0, 2, 2, 0.3);
+        // word 1 is below word 2, which prefers it as head
+        edge(1, 0, 1, 1.0);
+        edge(1, 2, 1, 1.0);
+        edge(1, 1, 2, 0.9);
+        let backbone = vec![vec![0, 0, 1, 2], vec![0, 0, 1]];
+        let lengths = [4, 3];
+        let decode = |policy: SdpPolicy| decode(&scores, None, &lengths, Some(&backbone), &policy);
+
+        let output = decode(SdpPolicy::default());
+        assert_eq!(
+            output[0],
+            vec![(1, 0), (1, 3), (2, 1), (2, 2), (3, 1), (3, 2)]
+        );
+
+        let output = decode(SdpPolicy {
+            max_parents: Some(1),
+            ..SdpPolicy::default()
+        });
+        assert_eq!(output[0], vec![(1, 0), (2, 1), (3, 2)]);
+
+        let output = decode(SdpPolicy {
+            remove_cycles: true,
+            ..SdpPolicy::default()
+        });
+        assert_eq!(output[0], vec![(1, 0), (2, 1), (3, 1), (3, 2)]);
+
+        let output = decode(SdpPolicy {
+            threshold: 1.5,
+            backbone: false,
+            ..SdpPolicy::default()
+        });
+        assert_eq!(output[0], vec![(1, 0)]);
+
+        let output = decode(SdpPolicy {
+            threshold: 1.5,
+            backbone: false,
+            connect: true,
+            ..SdpPolicy::default()
+        });
+        assert_eq!(output[0], vec![(1, 0), (2, 1), (3, 2)]);
+
+        let output = decode(SdpPolicy {
+            threshold: 0.5,
+            backbone: false,
+            remove_cycles: true,
+            ..SdpPolicy::default()
+        });
+        assert_eq!(output[1], vec![(1, 0), (1, 2)]);
+
+        let output = decode(SdpPolicy {
+            threshold: 0.95,
+            backbone: false,
+            connect: true,
+            ..SdpPolicy::default()
+        });
+        assert_eq!(output[1], vec![(1, 0), (1, 2), (2, 0)]);
+
+        // ranks below the threshold keep the edges, they only pick among them
+        let mut ranks = scores.iter().map(|x| x - 3.0).collect::<Vec<_>>();
+        ranks[3 * n + 1] = 5.0;
+        let output = super::decode(
+            &scores,
+            Some(&ranks),
+            &lengths,
+            Some(&backbone),
+            &SdpPolicy::default(),
+        );
+        assert_eq!(
+            output[0],
+            vec![(1, 0), (1, 3), (2, 1), (2, 2), (3, 1), (3, 2)]
+        );
+        let policy = SdpPolicy {
+            max_parents: Some(1),
+            backbone: false,
+            ..SdpPolicy::default()
+        };
+        let output = super::decode(&scores, None, &lengths, None, &policy);
+        assert_eq!(output[0], vec![(1, 0), (2, 1), (3, 2)]);
+        let output = super::decode(&scores, Some(&ranks), &lengths, None, &policy);
+        assert_eq!(output[0], vec![(1, 0), (2, 1), (3, 1)]);
+    }
+}
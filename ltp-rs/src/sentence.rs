@@ -0,0 +1,180 @@
+use itertools::Itertools;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sentence<'a> {
+    pub text: &'a str,
+    /// `[start, end)` char offsets in the source text
+    pub char_offsets: (usize, usize),
+    /// `[start, end)` UTF-8 byte offsets in the source text
+    pub byte_offsets: (usize, usize),
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '!' | '?' | '…')
+}
+
+/// closing quotes and brackets stay with the sentence they close
+fn is_closing(c: char) -> bool {
+    matches!(
+        c,
+        '”' | '’' | '」' | '』' | '"' | '\'' | '）' | ')' | '】' | '》' | ']'
+    )
+}
+
+/// closing quote of an opening quote
+fn closing_quote(c: char) -> Option<char> {
+    match c {
+        '“' => Some('”'),
+        '‘' => Some('’'),
+        '「' => Some('」'),
+        '『' => Some('』'),
+        '"' => Some('"'),
+        _ => None,
+    }
+}
+
+/// end of the terminator run starting at `start`, if there is one
+fn terminator_end(chars: &[char], start: usize) -> Option<usize> {
+    let c = chars[start];
+    if is_terminator(c) {
+        let run = chars[start..]
+            .iter()
+            .take_while(|&&c| is_terminator(c))
+            .count();
+        return Some(start + run);
+    }
+    if c == '.' {
+        // only dots followed by a space or the end, keep "3.14" and "ltp.rs" in one piece
+        let end = start + chars[start..].iter().take_while(|&&c| c == '.').count();
+        match chars[end..].iter().find(|&&c| !is_closing(c)) {
+            Some(c) if !c.is_whitespace() => {}
+            _ => return Some(end),
+        }
+    }
+    None
+}
+
+/// Split Chinese or English text into sentences, on terminal punctuations (`。！？!?`, `…`
+/// and `.` before a space) and line breaks. Closing quotes and brackets after a terminal
+/// punctuation (`”」』）`...) are kept in the sentence they close, surrounding whitespaces are
+/// trimmed and empty sentences are dropped.
+///
+/// Terminal punctuations inside quotes (`“”‘’「」『』"`) do not split, the sentence ends after
+/// the closing quote when a terminal punctuation comes right before it. Quotes left open on
+/// their line are ignored.
+pub fn split_sentences(text: &str) -> Vec<Sentence<'_>> {
+    let chars = text.chars().collect_vec();
+    let bytes = text
+        .char_indices()
+        .map(|(byte, _char)| byte)
+        .chain(std::iter::once(text.len()))
+        .collect_vec();
+
+    let is_line_break = |c: char| c == '\n' || c == '\r';
+    let mut spans = Vec::new();
+    // closing quotes of the open quotes, innermost last
+    let mut quotes = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        if is_line_break(c) {
+            spans.push((start, idx));
+            quotes.clear();
+            idx += 1;
+            start = idx;
+        } else if quotes.last() == Some(&c) {
+            quotes.pop();
+            idx += 1;
+            let before = chars[..idx - 1].iter().rev().find(|&&c| !is_closing(c));
+            if quotes.is_empty() && before.is_some_and(|&c| is_terminator(c) || c == '.') {
+                let end = idx + chars[idx..].iter().take_while(|&&c| is_closing(c)).count();
+                spans.push((start, end));
+                idx = end;
+                start = idx;
+            }
+        } else if let Some(closing) = closing_quote(c).filter(|&closing| {
+            chars[idx + 1..]
+                .iter()
+                .take_while(|&&c| !is_line_break(c))
+                .any(|&c| c == closing)
+        }) {
+            quotes.push(closing);
+            idx += 1;
+        } else if let Some(end) = terminator_end(&chars, idx) {
+            match quotes.is_empty() {
+                true => {
+                    let end = end + chars[end..].iter().take_while(|&&c| is_closing(c)).count();
+                    spans.push((start, end));
+                    idx = end;
+                    start = idx;
+                }
+                false => idx = end,
+            }
+        } else {
+            idx += 1;
+        }
+    }
+    spans.push((start, chars.len()));
+
+    spans
+        .into_iter()
+        .filter_map(|(mut start, mut end)| {
+            while start < end && chars[start].is_whitespace() {
+                start += 1;
+            }
+            while start < end && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            match start < end {
+                true => Some(Sentence {
+                    text: &text[bytes[start]..bytes[end]],
+                    char_offsets: (start, end),
+                    byte_offsets: (bytes[start], bytes[end]),
+                }),
+                false => None,
+            }
+        })
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sentence::split_sentences;
+
+    #[test]
+    fn test_split_sentences() {
+        let text = "他说：“我爱赛尔！”你呢？？\n「好……」 It costs 3.14 dollars. OK";
+        let sentences = split_sentences(text);
+        let texts: Vec<&str> = sentences.iter().map(|x| x.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "他说：“我爱赛尔！”",
+                "你呢？？",
+                "「好……」",
+                "It costs 3.14 dollars.",
+                "OK"
+            ]
+        );
+        assert_eq!(sentences[1].char_offsets, (10, 14));
+        assert_eq!(sentences[1].byte_offsets, (30, 42));
+        for sentence in sentences {
+            let (start, end) = sentence.byte_offsets;
+            assert_eq!(&text[start..end], sentence.text);
+        }
+
+        // quoted sentences stay in the sentence quoting them, unless the quote is left open
+        let text = "他说：“你好。我好。”她说：“嗯”。“好。\n再见。";
+        let texts: Vec<&str> = split_sentences(text).iter().map(|x| x.text).collect();
+        assert_eq!(
+            texts,
+            vec!["他说：“你好。我好。”", "她说：“嗯”。", "“好。", "再见。"]
+        );
+        let texts: Vec<&str> = split_sentences("He said \"Hi. Bye.\" Then left.")
+            .iter()
+            .map(|x| x.text)
+            .collect();
+        assert_eq!(texts, vec!["He said \"Hi. Bye.\"", "Then left."]);
+    }
+}
@@ -0,0 +1,209 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::time::{timeout_at, Instant};
+
+use crate::{LTPError, LTPResult, Result, Tasks, LTP};
+
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    /// max sentences run in one batch
+    pub max_batch_size: usize,
+    /// max time the first request of a batch waits for others to join
+    pub max_wait: Duration,
+    /// max requests waiting in the queue, new requests fail with `LTPError::Overloaded` when
+    /// the queue is full
+    pub queue_capacity: usize,
+    /// max batches running at the same time, should match the session pool size
+    pub concurrency: usize,
+    /// tasks run on every batch by the service created with `LTPService::new`
+    pub tasks: Tasks,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        ServiceConfig {
+            max_batch_size: 32,
+            max_wait: Duration::from_millis(5),
+            queue_capacity: 1024,
+            concurrency: 1,
+            tasks: Tasks::all(),
+        }
+    }
+}
+
+struct Request {
+    sentence: String,
+    reply: oneshot::Sender<Result<LTPResult>>,
+}
+
+/// Async front end of the pipeline, concurrent requests of single sentences are collected into
+/// batches by a background worker and the results are sent back to every caller.
+///
+/// The worker is spawned on the current tokio runtime, so the service must be created inside
+/// one.
+#[derive(Clone)]
+pub struct LTPService {
+    sender: mpsc::Sender<Request>,
+}
+
+impl LTPService {
+    pub fn new(ltp: Arc<LTP>, config: ServiceConfig) -> LTPService {
+        let tasks = config.tasks;
+        LTPService::with_handler(
+            move |sentences| ltp.pipeline_batch_with(&sentences, tasks),
+            config,
+        )
+    }
+
+    /// Batch the requests for any `handler` taking a batch of sentences.
+    pub fn with_handler<F>(handler: F, config: ServiceConfig) -> LTPService
+    where
+        F: Fn(Vec<String>) -> Result<Vec<LTPResult>> + Send + Sync + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+        tokio::spawn(worker(receiver, Arc::new(handler), config));
+        LTPService { sender }
+    }
+
+    pub async fn pipeline(&self, sentence: String) -> Result<LTPResult> {
+        let (reply, receiver) = oneshot::channel();
+        self.sender
+            .try_send(Request { sentence, reply })
+            .map_err(|err| match err {
+                TrySendError::Full(_) => LTPError::Overloaded,
+                TrySendError::Closed(_) => LTPError::ServiceClosed,
+            })?;
+        receiver.await.map_err(|_| LTPError::ServiceClosed)?
+    }
+}
+
+async fn worker<F>(mut receiver: mpsc::Receiver<Request>, handler: Arc<F>, config: ServiceConfig)
+where
+    F: Fn(Vec<String>) -> Result<Vec<LTPResult>> + Send + Sync + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(config.concurrency.max(1)));
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let deadline = Instant::now() + config.max_wait;
+        while batch.len() < config.max_batch_size {
+            match timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(request)) => batch.push(request),
+                _ => break,
+            }
+        }
+
+        let permit = match semaphore.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => break,
+        };
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let sentences = batch.iter().map(|x| x.sentence.clone()).collect();
+            let results = tokio::task::spawn_blocking(move || handler(sentences)).await;
+            drop(permit);
+
+            let results = match results {
+                Ok(results) => results,
+                Err(err) => Err(LTPError::ServiceError(err.to_string())),
+            };
+            let results = results.and_then(|results| {
+                if results.len() == batch.len() {
+                    Ok(results)
+                } else {
+                    Err(LTPError::ServiceError(format!(
+                        "handler returned {} results for {} sentences",
+                        results.len(),
+                        batch.len()
+                    )))
+                }
+            });
+            match results {
+                Ok(results) => {
+                    for (request, result) in batch.into_iter().zip(results) {
+                        let _ = request.reply.send(Ok(result));
+                    }
+                }
+                Err(err) => {
+                    for request in batch {
+                        let _ = request.reply.send(Err(copy_error(&err)));
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// `LTPError` is not `Clone`, the variants carrying plain data are copied to every request of
+/// the batch and the others are kept as their message.
+fn copy_error(err: &LTPError) -> LTPError {
+    match err {
+        LTPError::InvalidInput(message) => LTPError::InvalidInput(message.clone()),
+        LTPError::Overloaded => LTPError::Overloaded,
+        LTPError::ServiceClosed => LTPError::ServiceClosed,
+        LTPError::ServiceError(message) => LTPError::ServiceError(message.clone()),
+        err => LTPError::ServiceError(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::service::{LTPService, ServiceConfig};
+    use crate::{LTPError, LTPResult};
+
+    #[tokio::test]
+    async fn test_service() {
+        let batches = Arc::new(AtomicUsize::new(0));
+        let counter = batches.clone();
+        let config = ServiceConfig {
+            max_batch_size: 4,
+            max_wait: Duration::from_millis(50),
+            queue_capacity: 8,
+            ..ServiceConfig::default()
+        };
+        let service = LTPService::with_handler(
+            move |sentences| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(sentences
+                    .into_iter()
+                    .map(|x| LTPResult {
+                        seg: Some(vec![x]),
+                        ..LTPResult::default()
+                    })
+                    .collect())
+            },
+            config,
+        );
+
+        let requests = (0..8).map(|idx| {
+            let service = service.clone();
+            tokio::spawn(async move { service.pipeline(format!("{}", idx)).await })
+        });
+        for (idx, request) in requests.collect::<Vec<_>>().into_iter().enumerate() {
+            let result = request.await.unwrap().unwrap();
+            assert_eq!(result.seg.unwrap(), vec![format!("{}", idx)]);
+        }
+        assert!(batches.load(Ordering::SeqCst) < 8);
+
+        let service = LTPService::with_handler(
+            |_sentences| Err(LTPError::InvalidInput(String::from("boom"))),
+            ServiceConfig::default(),
+        );
+        match service.pipeline(String::from("x")).await {
+            Err(LTPError::InvalidInput(message)) => assert_eq!(message, "boom"),
+            _ => panic!("the error should be fanned out"),
+        }
+
+        let service = LTPService::with_handler(|_sentences| Ok(vec![]), ServiceConfig::default());
+        match service.pipeline(String::from("x")).await {
+            Err(LTPError::ServiceError(message)) => assert!(message.contains("0 results")),
+            _ => panic!("missing results should be reported"),
+        }
+    }
+}
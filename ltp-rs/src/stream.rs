@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread;
+
+use crate::{BatchPolicy, LTPResult, Result, Tasks, LTP};
+
+enum Source<I> {
+    Direct(I),
+    Prefetch(Receiver<Vec<String>>),
+    Done,
+}
+
+/// Lazy iterator over the results of a stream of sentences, built by [`LTP::analyze_iter`].
+///
+/// Sentences are read `batch_size` at a time and run grouped by length, see
+/// [`LTP::pipeline_batch_bucketed`], the results are yielded in input order. An error of a
+/// batch is yielded once and ends the iteration.
+pub struct AnalyzeIter<'a, I> {
+    ltp: &'a LTP,
+    source: Source<I>,
+    batch_size: usize,
+    tasks: Tasks,
+    policy: BatchPolicy,
+    buffer: VecDeque<LTPResult>,
+}
+
+impl<'a, I> AnalyzeIter<'a, I>
+where
+    I: Iterator<Item = String>,
+{
+    pub(crate) fn new(ltp: &'a LTP, inputs: I, batch_size: usize) -> AnalyzeIter<'a, I> {
+        AnalyzeIter {
+            ltp,
+            source: Source::Direct(inputs),
+            batch_size: batch_size.max(1),
+            tasks: Tasks::all(),
+            policy: BatchPolicy {
+                max_batch_size: batch_size.max(1),
+                max_tokens: None,
+            },
+            buffer: VecDeque::new(),
+        }
+    }
+
+    pub fn tasks(mut self, tasks: Tasks) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// How the sentences read together are grouped into the batches run by the model,
+    /// defaults to batches of up to `batch_size` sentences without a token limit.
+    pub fn batch_policy(mut self, policy: BatchPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Read the next batch from the inputs in a background thread while the current one
+    /// runs, for inputs that are slow to produce such as files or network streams.
+    pub fn prefetch(mut self) -> Self
+    where
+        I: Send + 'static,
+    {
+        if let Source::Direct(mut inputs) = std::mem::replace(&mut self.source, Source::Done) {
+            let (sender, receiver) = sync_channel(1);
+            let batch_size = self.batch_size;
+            thread::spawn(move || loop {
+                let batch: Vec<String> = inputs.by_ref().take(batch_size).collect();
+                if batch.is_empty() || sender.send(batch).is_err() {
+                    break;
+                }
+            });
+            self.source = Source::Prefetch(receiver);
+        }
+        self
+    }
+
+    fn next_batch(&mut self) -> Vec<String> {
+        match &mut self.source {
+            Source::Direct(inputs) => inputs.by_ref().take(self.batch_size).collect(),
+            Source::Prefetch(receiver) => receiver.recv().unwrap_or_default(),
+            Source::Done => Vec::new(),
+        }
+    }
+}
+
+impl<'a, I> Iterator for AnalyzeIter<'a, I>
+where
+    I: Iterator<Item = String>,
+{
+    type Item = Result<LTPResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() {
+            let batch = self.next_batch();
+            if batch.is_empty() {
+                self.source = Source::Done;
+                return None;
+            }
+            match self
+                .ltp
+                .pipeline_batch_bucketed(&batch, self.tasks, self.policy)
+            {
+                Ok(results) => self.buffer.extend(results),
+                Err(err) => {
+                    self.source = Source::Done;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
@@ -1,4 +1,5 @@
-use num_traits::PrimInt;
+use num_traits::{Float, PrimInt};
+use std::cmp::Ordering;
 
 pub fn viterbi_decode_postprocess<T>(
     history: &[T],
@@ -41,12 +42,336 @@ where
     result
 }
 
+/// Scores of a linear-chain CRF, see [`crf_forward`] for the layout.
+struct Crf<'a, T> {
+    emissions: &'a [T],
+    transitions: &'a [T],
+    start: Option<&'a [T]>,
+    end: Option<&'a [T]>,
+    labels_num: usize,
+    max_stn_len: usize,
+}
+
+impl<'a, T: Float> Crf<'a, T> {
+    fn new(
+        emissions: &'a [T],
+        transitions: &'a [T],
+        start: Option<&'a [T]>,
+        end: Option<&'a [T]>,
+        stn_lengths: &[usize],
+        labels_num: usize,
+    ) -> Self {
+        Crf {
+            emissions,
+            transitions,
+            start,
+            end,
+            labels_num,
+            max_stn_len: stn_lengths.iter().cloned().max().unwrap_or(0),
+        }
+    }
+
+    fn emission(&self, stn_idx: usize, t: usize, label: usize) -> T {
+        self.emissions[(stn_idx * self.max_stn_len + t) * self.labels_num + label]
+    }
+
+    fn transition(&self, prev: usize, label: usize) -> T {
+        self.transitions[prev * self.labels_num + label]
+    }
+
+    fn start(&self, label: usize) -> T {
+        self.start.map_or(T::zero(), |start| start[label])
+    }
+
+    fn end(&self, label: usize) -> T {
+        self.end.map_or(T::zero(), |end| end[label])
+    }
+
+    /// log of the summed exp of the scores of every tag at every position, from the start
+    fn alphas(&self, stn_idx: usize, stn_len: usize) -> Vec<Vec<T>> {
+        let mut alphas = Vec::with_capacity(stn_len);
+        alphas.push(
+            (0..self.labels_num)
+                .map(|label| self.start(label) + self.emission(stn_idx, 0, label))
+                .collect::<Vec<_>>(),
+        );
+        for t in 1..stn_len {
+            let prev = &alphas[t - 1];
+            let alpha = (0..self.labels_num)
+                .map(|label| {
+                    log_sum_exp((0..self.labels_num).map(|p| prev[p] + self.transition(p, label)))
+                        + self.emission(stn_idx, t, label)
+                })
+                .collect();
+            alphas.push(alpha);
+        }
+        alphas
+    }
+
+    /// same as `alphas` but from the end
+    fn betas(&self, stn_idx: usize, stn_len: usize) -> Vec<Vec<T>> {
+        let mut betas = vec![Vec::new(); stn_len];
+        betas[stn_len - 1] = (0..self.labels_num).map(|label| self.end(label)).collect();
+        for t in (0..stn_len - 1).rev() {
+            let beta = (0..self.labels_num)
+                .map(|label| {
+                    log_sum_exp((0..self.labels_num).map(|next| {
+                        self.transition(label, next)
+                            + self.emission(stn_idx, t + 1, next)
+                            + betas[t + 1][next]
+                    }))
+                })
+                .collect();
+            betas[t] = beta;
+        }
+        betas
+    }
+}
+
+fn log_sum_exp<T: Float, I: Iterator<Item = T>>(scores: I) -> T {
+    let scores = scores.collect::<Vec<_>>();
+    let max = scores.iter().cloned().fold(T::neg_infinity(), T::max);
+    if max == T::neg_infinity() {
+        return max;
+    }
+    max + scores
+        .iter()
+        .map(|&x| (x - max).exp())
+        .fold(T::zero(), |a, b| a + b)
+        .ln()
+}
+
+/// Decode the best tag path of every sequence with its score from emission and transition
+/// scores, the `start` and `end` scores are optional. See [`crf_forward`] for the layout.
+pub fn viterbi_decode<T>(
+    emissions: &[T],
+    transitions: &[T],
+    start: Option<&[T]>,
+    end: Option<&[T]>,
+    stn_lengths: &[usize],
+    labels_num: usize,
+) -> Vec<(Vec<usize>, T)>
+where
+    T: Float,
+{
+    let crf = Crf::new(emissions, transitions, start, end, stn_lengths, labels_num);
+
+    let mut result = Vec::new();
+    for (stn_idx, &stn_len) in stn_lengths.iter().enumerate() {
+        if stn_len == 0 {
+            result.push((Vec::new(), T::zero()));
+            continue;
+        }
+        let mut scores = (0..labels_num)
+            .map(|label| crf.start(label) + crf.emission(stn_idx, 0, label))
+            .collect::<Vec<_>>();
+        let mut history = Vec::with_capacity(stn_len - 1);
+        for t in 1..stn_len {
+            let mut next = Vec::with_capacity(labels_num);
+            let mut best = Vec::with_capacity(labels_num);
+            for label in 0..labels_num {
+                let (prev, score) = (0..labels_num)
+                    .map(|prev| (prev, scores[prev] + crf.transition(prev, label)))
+                    .fold((0, T::neg_infinity()), |a, b| if b.1 > a.1 { b } else { a });
+                next.push(score + crf.emission(stn_idx, t, label));
+                best.push(prev);
+            }
+            scores = next;
+            history.push(best);
+        }
+
+        let (mut label, score) = (0..labels_num)
+            .map(|label| (label, scores[label] + crf.end(label)))
+            .fold((0, T::neg_infinity()), |a, b| if b.1 > a.1 { b } else { a });
+        let mut path = vec![label];
+        for best in history.iter().rev() {
+            label = best[label];
+            path.push(label);
+        }
+        path.reverse();
+        result.push((path, score));
+    }
+    result
+}
+
+/// Decode the `k` best tag paths of every sequence from emission and transition scores, the
+/// `start` and `end` scores are optional. See [`crf_forward`] for the layout.
+///
+/// Paths of every sequence are returned best first with their scores, fewer than `k` when
+/// there are not as many allowed paths.
+pub fn viterbi_decode_kbest<T>(
+    emissions: &[T],
+    transitions: &[T],
+    start: Option<&[T]>,
+    end: Option<&[T]>,
+    stn_lengths: &[usize],
+    labels_num: usize,
+    k: usize,
+) -> Vec<Vec<(Vec<usize>, T)>>
+where
+    T: Float,
+{
+    let crf = Crf::new(emissions, transitions, start, end, stn_lengths, labels_num);
+    let k = k.max(1);
+    let by_score = |a: &(T, usize, usize), b: &(T, usize, usize)| {
+        b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal)
+    };
+
+    let mut result = Vec::new();
+    for (stn_idx, &stn_len) in stn_lengths.iter().enumerate() {
+        if stn_len == 0 {
+            result.push(Vec::new());
+            continue;
+        }
+
+        // beams[t][label] holds the best (score, previous label, rank in the previous beam)
+        // of the paths ending with `label` at `t`
+        let mut beams: Vec<Vec<Vec<(T, usize, usize)>>> = Vec::with_capacity(stn_len);
+        beams.push(
+            (0..labels_num)
+                .map(|label| crf.start(label) + crf.emission(stn_idx, 0, label))
+                .map(|score| match score > T::neg_infinity() {
+                    true => vec![(score, 0, 0)],
+                    false => Vec::new(),
+                })
+                .collect(),
+        );
+        for t in 1..stn_len {
+            let prev = &beams[t - 1];
+            let beam = (0..labels_num)
+                .map(|label| {
+                    let mut candidates = Vec::new();
+                    for (prev_label, paths) in prev.iter().enumerate() {
+                        let transition = crf.transition(prev_label, label);
+                        for (rank, &(score, _, _)) in paths.iter().enumerate() {
+                            let score = score + transition + crf.emission(stn_idx, t, label);
+                            if score > T::neg_infinity() {
+                                candidates.push((score, prev_label, rank));
+                            }
+                        }
+                    }
+                    candidates.sort_by(by_score);
+                    candidates.truncate(k);
+                    candidates
+                })
+                .collect();
+            beams.push(beam);
+        }
+
+        let mut ends = beams[stn_len - 1]
+            .iter()
+            .enumerate()
+            .flat_map(|(label, paths)| {
+                let end = crf.end(label);
+                paths
+                    .iter()
+                    .enumerate()
+                    .map(move |(rank, &(score, _, _))| (score + end, label, rank))
+            })
+            .filter(|&(score, _, _)| score > T::neg_infinity())
+            .collect::<Vec<_>>();
+        ends.sort_by(by_score);
+        ends.truncate(k);
+
+        let paths = ends
+            .into_iter()
+            .map(|(score, mut label, mut rank)| {
+                let mut path = vec![label];
+                for t in (1..stn_len).rev() {
+                    let (_, prev_label, prev_rank) = beams[t][label][rank];
+                    path.push(prev_label);
+                    label = prev_label;
+                    rank = prev_rank;
+                }
+                path.reverse();
+                (path, score)
+            })
+            .collect();
+        result.push(paths);
+    }
+    result
+}
+
+/// Log partition function of every sequence, computed by the forward algorithm. The
+/// probability of a path is `exp(score - log_partition)`.
+///
+/// `emissions` is `[stn_num, max_stn_len, labels_num]` where `max_stn_len` is the max of
+/// `stn_lengths`, `transitions` is `[labels_num, labels_num]` from the previous tag to the next
+/// one, the optional `start` and `end` are `[labels_num]` scores of the first and the last
+/// tag. Use `-inf` to forbid a transition.
+pub fn crf_forward<T>(
+    emissions: &[T],
+    transitions: &[T],
+    start: Option<&[T]>,
+    end: Option<&[T]>,
+    stn_lengths: &[usize],
+    labels_num: usize,
+) -> Vec<T>
+where
+    T: Float,
+{
+    let crf = Crf::new(emissions, transitions, start, end, stn_lengths, labels_num);
+    stn_lengths
+        .iter()
+        .enumerate()
+        .map(|(stn_idx, &stn_len)| match stn_len {
+            0 => T::zero(),
+            _ => {
+                let alphas = crf.alphas(stn_idx, stn_len);
+                let last = &alphas[stn_len - 1];
+                log_sum_exp((0..labels_num).map(|label| last[label] + crf.end(label)))
+            }
+        })
+        .collect()
+}
+
+/// Marginal probability of every tag at every position, computed by the forward-backward
+/// algorithm, `[stn_len, labels_num]` for every sequence. See [`crf_forward`] for the layout.
+pub fn crf_marginals<T>(
+    emissions: &[T],
+    transitions: &[T],
+    start: Option<&[T]>,
+    end: Option<&[T]>,
+    stn_lengths: &[usize],
+    labels_num: usize,
+) -> Vec<Vec<T>>
+where
+    T: Float,
+{
+    let crf = Crf::new(emissions, transitions, start, end, stn_lengths, labels_num);
+    stn_lengths
+        .iter()
+        .enumerate()
+        .map(|(stn_idx, &stn_len)| {
+            if stn_len == 0 {
+                return Vec::new();
+            }
+            let alphas = crf.alphas(stn_idx, stn_len);
+            let betas = crf.betas(stn_idx, stn_len);
+            let last = &alphas[stn_len - 1];
+            let log_partition =
+                log_sum_exp((0..labels_num).map(|label| last[label] + crf.end(label)));
+            alphas
+                .iter()
+                .zip(&betas)
+                .flat_map(|(alpha, beta)| {
+                    (0..labels_num)
+                        .map(move |label| (alpha[label] + beta[label] - log_partition).exp())
+                })
+                .collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use ndarray_npy::{NpzReader, ReadNpzError};
     use std::fs::File;
 
-    use crate::viterbi::viterbi_decode_postprocess;
+    use crate::viterbi::{
+        crf_forward, crf_marginals, viterbi_decode, viterbi_decode_kbest,
+        viterbi_decode_postprocess,
+    };
     use ndarray::{Array1, Array3};
 
     #[test]
@@ -74,4 +399,135 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_viterbi_kbest() {
+        // 2 sequences of 3 labels, the second one is padded
+        let emissions = vec![
+            1.0, 0.5, 0.0, 0.2, 0.9, 0.1, 0.3, 0.3, 0.8, // first
+            0.1, 0.7, 0.2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, // second
+        ];
+        let mut transitions = vec![0.1f64; 9];
+        // 0 -> 1 is forbidden, 2 -> 0 is preferred
+        transitions[1] = f64::NEG_INFINITY;
+        transitions[6] = 0.55;
+
+        let output = viterbi_decode_kbest(&emissions, &transitions, None, None, &[3, 1], 3, 4);
+
+        // brute force over the allowed paths of the first sequence
+        let mut paths = Vec::new();
+        for a in 0..3 {
+            for b in 0..3 {
+                for c in 0..3 {
+                    let score = emissions[a]
+                        + emissions[3 + b]
+                        + emissions[6 + c]
+                        + transitions[a * 3 + b]
+                        + transitions[b * 3 + c];
+                    if score > f64::NEG_INFINITY {
+                        paths.push((vec![a, b, c], score));
+                    }
+                }
+            }
+        }
+        paths.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        assert_eq!(output[0].len(), 4);
+        for ((path, score), (correct, correct_score)) in output[0].iter().zip(&paths) {
+            assert_eq!(path, correct);
+            assert!((score - correct_score).abs() < 1e-9);
+        }
+        assert_eq!(
+            output[1],
+            vec![(vec![1], 0.7), (vec![2], 0.2), (vec![0], 0.1)]
+        );
+    }
+
+    #[test]
+    fn test_crf() {
+        let emissions = vec![
+            1.0, 0.5, 0.0, 0.2, 0.9, 0.1, 0.3, 0.3, 0.8, // first
+            0.1, 0.7, 0.2, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, // second
+        ];
+        let transitions = vec![0.1, f64::NEG_INFINITY, 0.2, 0.3, 0.0, 0.4, 0.55, 0.1, 0.1];
+        let start = vec![0.0, 0.3, f64::NEG_INFINITY];
+        let end = vec![0.2, 0.0, 0.1];
+
+        // brute force over the paths of the first sequence
+        let mut paths = Vec::new();
+        for a in 0..3 {
+            for b in 0..3 {
+                for c in 0..3 {
+                    let score = start[a]
+                        + emissions[a]
+                        + emissions[3 + b]
+                        + emissions[6 + c]
+                        + transitions[a * 3 + b]
+                        + transitions[b * 3 + c]
+                        + end[c];
+                    paths.push((vec![a, b, c], score));
+                }
+            }
+        }
+        paths.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        let log_partition = paths.iter().map(|x| x.1.exp()).sum::<f64>().ln();
+
+        let best = viterbi_decode(
+            &emissions,
+            &transitions,
+            Some(&start),
+            Some(&end),
+            &[3, 1],
+            3,
+        );
+        assert_eq!(best[0].0, paths[0].0);
+        assert!((best[0].1 - paths[0].1).abs() < 1e-9);
+        assert_eq!(best[1].0, vec![1]);
+        assert!((best[1].1 - 1.0).abs() < 1e-9);
+
+        let kbest = viterbi_decode_kbest(
+            &emissions,
+            &transitions,
+            Some(&start),
+            Some(&end),
+            &[3, 1],
+            3,
+            2,
+        );
+        assert_eq!(kbest[0][0].0, paths[0].0);
+        assert_eq!(kbest[0][1].0, paths[1].0);
+
+        let partition = crf_forward(
+            &emissions,
+            &transitions,
+            Some(&start),
+            Some(&end),
+            &[3, 1],
+            3,
+        );
+        assert!((partition[0] - log_partition).abs() < 1e-9);
+
+        let marginals = crf_marginals(
+            &emissions,
+            &transitions,
+            Some(&start),
+            Some(&end),
+            &[3, 1],
+            3,
+        );
+        for t in 0..3 {
+            let correct = (0..3)
+                .map(|label| {
+                    paths
+                        .iter()
+                        .filter(|x| x.0[t] == label)
+                        .map(|x| (x.1 - log_partition).exp())
+                        .sum::<f64>()
+                })
+                .collect::<Vec<_>>();
+            for label in 0..3 {
+                assert!((marginals[0][t * 3 + label] - correct[label]).abs() < 1e-9);
+            }
+        }
+        assert_eq!(marginals[1].len(), 3);
+    }
 }
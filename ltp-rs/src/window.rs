@@ -0,0 +1,64 @@
+/// How good a cut after a unit is, higher is better. Sentence ends found by
+/// [`crate::sentence::split_sentences`] should be raised to `2` as well.
+pub(crate) fn boundary_level(c: char) -> u8 {
+    match c {
+        '。' | '！' | '？' | '!' | '?' | '…' | '\n' => 2,
+        '，' | ',' | '；' | ';' | '、' | '：' | ':' => 1,
+        _ => 0,
+    }
+}
+
+/// Split units (chars or words) into windows of at most `limit` in total length, cutting
+/// after the best boundary of each window. Boundaries in the second half of a window are
+/// preferred so the windows don't shrink too much, the window is cut hard when there is no
+/// boundary at all.
+///
+/// Returns `[start, end)` ranges of unit indices, an empty input is kept as one empty window.
+pub(crate) fn split(lengths: &[usize], levels: &[u8], limit: usize) -> Vec<(usize, usize)> {
+    if lengths.is_empty() {
+        return vec![(0, 0)];
+    }
+    let limit = limit.max(1);
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < lengths.len() {
+        let mut total = 0;
+        let mut end = start;
+        while end < lengths.len() && (end == start || total + lengths[end] <= limit) {
+            total += lengths[end];
+            end += 1;
+        }
+        if end < lengths.len() {
+            let half = start + (end - start) / 2;
+            let best = (start..end)
+                .filter(|&idx| levels[idx] > 0)
+                .max_by_key(|&idx| (idx >= half, levels[idx], idx));
+            if let Some(best) = best {
+                end = best + 1;
+            }
+        }
+        windows.push((start, end));
+        start = end;
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::window::{boundary_level, split};
+
+    #[test]
+    fn test_split() {
+        let text: Vec<char> = "他叫汤姆去拿外衣。我爱赛尔，你呢？".chars().collect();
+        let lengths = vec![1; text.len()];
+        let levels: Vec<u8> = text.iter().map(|&c| boundary_level(c)).collect();
+
+        assert_eq!(split(&[], &[], 32), vec![(0, 0)]);
+        assert_eq!(split(&lengths, &levels, 32), vec![(0, 17)]);
+        assert_eq!(split(&lengths, &levels, 16), vec![(0, 9), (9, 17)]);
+        assert_eq!(
+            split(&lengths, &levels, 6),
+            vec![(0, 6), (6, 9), (9, 14), (14, 17)]
+        );
+    }
+}